@@ -0,0 +1,229 @@
+use crate::Error;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::NamedTempFile;
+
+/// Where the outputs of a batch go.
+#[derive(Debug, Clone, Copy)]
+pub enum Destination<'a> {
+    /// A directory, mirroring the relative paths of the inputs
+    Dir(&'a Path),
+    /// Over the inputs, keeping each original as `.bak`
+    InPlace,
+}
+
+/// How a batch went.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub total: usize,
+    pub failed: usize,
+}
+
+/// Drop files listed more than once (e.g. by a path and an overlapping glob), keeping the first.
+///
+/// Otherwise `--in-place` would run the query on its own output, and back that up instead.
+#[must_use]
+pub fn unique(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = std::collections::HashSet::new();
+    files
+        .into_iter()
+        .filter(|file| seen.insert(file.canonicalize().unwrap_or_else(|_| file.clone())))
+        .collect()
+}
+
+/// Run the command built for each file, saving each output to the destination.
+///
+/// A file that fails doesn't stop the others. Each failure is reported on stderr as it happens,
+/// after whatever jq printed about it.
+pub fn batch(
+    files: &[PathBuf],
+    destination: Destination,
+    command: impl Fn(&Path) -> String,
+) -> Summary {
+    let mut failed = 0;
+    for file in files {
+        if let Err(err) = apply_one(file, destination, &command(file)) {
+            eprintln!("{}: {err}", file.display());
+            failed += 1;
+        }
+    }
+
+    Summary {
+        total: files.len(),
+        failed,
+    }
+}
+
+fn apply_one(file: &Path, destination: Destination, command: &str) -> Result<(), Error> {
+    let output = output(command)?;
+    if output.is_empty() {
+        return Err(Error::NoOutput(file.to_path_buf()));
+    }
+
+    match destination {
+        Destination::Dir(dir) => {
+            let path = output_path(dir, file);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, output)?;
+        }
+        Destination::InPlace => {
+            replace_with_backup(file, &output)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where the output for `file` goes in `dir`.
+///
+/// Relative paths are kept, so files with the same name in different directories don't collide.
+/// Anything pointing outside of the current directory only keeps its file name.
+fn output_path(dir: &Path, file: &Path) -> PathBuf {
+    let stays_inside = file
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if stays_inside {
+        dir.join(file)
+    } else {
+        dir.join(file.file_name().unwrap_or(file.as_os_str()))
+    }
+}
+
+/// Run a jq command with bash (which it's quoted for), returning what it printed.
+pub fn output(command: &str) -> Result<Vec<u8>, Error> {
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::Command {
+            command: command.to_string(),
+            status: output.status,
+        })
+    }
+}
+
+/// Replace a file with new contents all at once, keeping the old version next to it as `.bak`.
+///
+/// Symlinks are followed, so the file they point to is replaced rather than the link. Returns
+/// where the backup was written.
+pub fn replace_with_backup(path: &Path, contents: &[u8]) -> Result<PathBuf, Error> {
+    let path = path.canonicalize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut replacement = NamedTempFile::new_in(dir)?;
+    replacement.write_all(contents)?;
+    replacement
+        .as_file()
+        .set_permissions(std::fs::metadata(&path)?.permissions())?;
+
+    let backup = backup_path(&path);
+    std::fs::copy(&path, &backup)?;
+    replacement.persist(&path)?;
+
+    Ok(backup)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    backup.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_and_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.json");
+        std::fs::write(&file, "{\"a\":1}\n").unwrap();
+        let link = dir.path().join("link.json");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        let backup = replace_with_backup(&link, b"{\"a\":2}\n").unwrap();
+
+        assert_eq!(
+            backup,
+            file.canonicalize().unwrap().with_extension("json.bak")
+        );
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "{\"a\":1}\n");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "{\"a\":2}\n");
+        assert!(link.is_symlink());
+    }
+
+    #[test]
+    fn files_listed_twice_run_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.json");
+        std::fs::write(&file, "{}").unwrap();
+        let other = dir.path().join("b.json");
+
+        let files = unique(vec![
+            file.clone(),
+            other.clone(),
+            dir.path().join("./a.json"),
+        ]);
+        assert_eq!(files, [file, other]);
+    }
+
+    #[test]
+    fn outputs_mirror_relative_paths() {
+        let dir = Path::new("out");
+        assert_eq!(
+            output_path(dir, Path::new("./a/b.json")),
+            Path::new("out/a/b.json")
+        );
+        assert_eq!(
+            output_path(dir, Path::new("../c.json")),
+            Path::new("out/c.json")
+        );
+        assert_eq!(
+            output_path(dir, Path::new("/tmp/d.json")),
+            Path::new("out/d.json")
+        );
+    }
+
+    #[test]
+    fn batch_reports_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let files = [PathBuf::from("good.json"), PathBuf::from("bad.json")];
+
+        let summary = batch(&files, Destination::Dir(&out), |file| {
+            if file == Path::new("good.json") {
+                "echo 1".to_string()
+            } else {
+                "exit 5".to_string()
+            }
+        });
+
+        assert_eq!(
+            summary,
+            Summary {
+                total: 2,
+                failed: 1
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("good.json")).unwrap(),
+            "1\n"
+        );
+        assert!(!out.join("bad.json").exists());
+    }
+
+    #[test]
+    fn failed_command_is_an_error() {
+        assert_eq!(output("printf ok").unwrap(), b"ok");
+        assert!(matches!(output("exit 3"), Err(Error::Command { .. })));
+    }
+}
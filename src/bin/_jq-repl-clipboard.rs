@@ -0,0 +1,76 @@
+use base64::Engine;
+use clap::Parser;
+use std::io::{Read, Write};
+
+/// Copy text to the terminal's clipboard, using the OSC 52 escape sequence.
+///
+/// This works over SSH, since the terminal does the copying rather than a program like `xclip`.
+/// The terminal has to allow it, and some limit how much can be copied at once.
+///
+/// Inside tmux, the sequence is passed through to the outer terminal, which needs
+/// `set -g allow-passthrough on` in tmux 3.3 and later.
+#[derive(Debug, Parser)]
+#[command(name = "_jq-repl-clipboard", version, verbatim_doc_comment)]
+struct ClipboardOpts {
+    /// Text to copy, joined with spaces (defaults to standard input)
+    text: Vec<String>,
+
+    /// Where to write the sequence, which has to reach the terminal even when output is redirected
+    #[arg(long, default_value = "/dev/tty")]
+    tty: std::path::PathBuf,
+}
+
+fn main() -> std::io::Result<()> {
+    let opts = ClipboardOpts::parse();
+
+    let text = if opts.text.is_empty() {
+        let mut text = vec![];
+        std::io::stdin().read_to_end(&mut text)?;
+        text
+    } else {
+        opts.text.join(" ").into_bytes()
+    };
+
+    let mut sequence = osc52(&text);
+    if std::env::var_os("TMUX").is_some() {
+        sequence = tmux_passthrough(&sequence);
+    }
+
+    let mut tty = std::fs::OpenOptions::new().write(true).open(&opts.tty)?;
+    tty.write_all(sequence.as_bytes())?;
+    tty.flush()
+}
+
+/// Ask the terminal to put `text` on the clipboard.
+fn osc52(text: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    format!("\x1b]52;c;{encoded}\x07")
+}
+
+/// Wrap a sequence so tmux hands it to the outer terminal, instead of handling it itself.
+fn tmux_passthrough(sequence: &str) -> String {
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_args() {
+        <ClipboardOpts as clap::CommandFactory>::command().debug_assert();
+    }
+
+    #[test]
+    fn encodes_text() {
+        assert_eq!(osc52(b".a | keys"), "\x1b]52;c;LmEgfCBrZXlz\x07");
+    }
+
+    #[test]
+    fn escapes_for_tmux() {
+        assert_eq!(
+            tmux_passthrough("\x1b]52;c;YQ==\x07"),
+            "\x1bPtmux;\x1b\x1b]52;c;YQ==\x07\x1b\\"
+        );
+    }
+}
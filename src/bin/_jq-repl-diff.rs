@@ -0,0 +1,210 @@
+use clap::Parser;
+use jq_repl::{
+    Prompt,
+    diff::{Change, diff, parse_output, render},
+    transform::PINNED_PROGRAM,
+};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+
+/// Show how the output of a query differs between two inputs, by JSON path.
+///
+/// The query is run on each input separately, the same way the preview runs it. With `--pinned`,
+/// it's run on all inputs together instead, and compared against output saved earlier with
+/// `--save`.
+///
+/// The jq binary and its arguments are read from the environment variables set by `jq-repl`:
+///
+/// | Name                    | Description                                               |
+/// |-------------------------+-----------------------------------------------------------|
+/// | `JQ_REPL_JQ_BIN`        | jq binary name (e.g. "gojq")                              |
+/// | `JQ_REPL_JQ_ARG_PREFIX` | static jq arguments (library paths, `--raw-output`, etc.) |
+#[derive(Debug, Parser)]
+#[command(name = "_jq-repl-diff", version, verbatim_doc_comment)]
+struct DiffOpts {
+    /// Compare against the output saved in this file
+    #[arg(long, value_name = "FILE", conflicts_with = "save")]
+    pinned: Option<PathBuf>,
+
+    /// Save the output to this file, to compare against later with `--pinned`
+    #[arg(long, value_name = "FILE")]
+    save: Option<PathBuf>,
+
+    /// Only print whether the output matches, for the preview label
+    ///
+    /// Nothing is printed unless the prompt shows the pinned lens is active.
+    #[arg(long, requires = "pinned")]
+    label: bool,
+
+    /// The current prompt, which shows the active lens
+    #[arg(long, allow_hyphen_values = true, env = "FZF_PROMPT")]
+    prompt: Option<String>,
+
+    /// The query to run
+    #[arg(allow_hyphen_values = true)]
+    query: String,
+
+    /// The inputs to run it on, from old to new when comparing two of them
+    inputs: Vec<PathBuf>,
+}
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let opts = DiffOpts::parse();
+    let jq_bin = std::env::var("JQ_REPL_JQ_BIN")?;
+    let prefix = std::env::var("JQ_REPL_JQ_ARG_PREFIX").unwrap_or_default();
+    let run = |inputs: &[PathBuf]| run_query(&jq_bin, &prefix, &opts.query, inputs);
+
+    if let Some(save) = &opts.save {
+        let Some(output) = run(&opts.inputs)? else {
+            return Ok(ExitCode::FAILURE);
+        };
+        std::fs::write(save, output)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let (old_name, old, new_name, new) = if let Some(pinned) = &opts.pinned {
+        if opts.label && !pinned_lens_active(opts.prompt.as_deref()) {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let Some(pinned) = read_pinned(pinned)? else {
+            if !opts.label {
+                println!("Nothing has been pinned yet");
+            }
+            return Ok(ExitCode::SUCCESS);
+        };
+        // Errors are left for the preview to show
+        let Some(live) = run(&opts.inputs)? else {
+            return Ok(ExitCode::FAILURE);
+        };
+        ("pinned".into(), pinned, "live".into(), live)
+    } else {
+        let [old_input, new_input] = opts.inputs.as_slice() else {
+            let err = <DiffOpts as clap::CommandFactory>::command().error(
+                clap::error::ErrorKind::WrongNumberOfValues,
+                "exactly two inputs are needed to compare them",
+            );
+            err.exit();
+        };
+        let (Some(old), Some(new)) = (
+            run(std::slice::from_ref(old_input))?,
+            run(std::slice::from_ref(new_input))?,
+        ) else {
+            // jq has already explained what went wrong on stderr
+            return Ok(ExitCode::FAILURE);
+        };
+        (
+            old_input.to_string_lossy(),
+            old,
+            new_input.to_string_lossy(),
+            new,
+        )
+    };
+
+    let changes = diff(&parse_output(&old), &parse_output(&new));
+    if opts.label {
+        print!("{}", label(&changes));
+    } else {
+        print!("{}", render(&old_name, &new_name, &changes));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The output saved with `--save`, if anything has been pinned yet.
+fn read_pinned(pinned: &Path) -> std::io::Result<Option<String>> {
+    match std::fs::read_to_string(pinned) {
+        Ok(pinned) => Ok(Some(pinned)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether the output matches, for the preview label.
+fn label(changes: &[Change]) -> String {
+    match changes.len() {
+        0 => " identical ".to_string(),
+        1 => " 1 difference ".to_string(),
+        n => format!(" {n} differences "),
+    }
+}
+
+fn pinned_lens_active(prompt: Option<&str>) -> bool {
+    prompt
+        .and_then(|prompt| prompt.parse::<Prompt>().ok())
+        .is_some_and(|prompt| prompt.program() == Some(PINNED_PROGRAM))
+}
+
+/// Run the query with compact output, so each value is on its own line.
+///
+/// Without any inputs, it reads standard input, like the preview does with `--pass-as-stdin`.
+fn run_query(
+    jq_bin: &str,
+    prefix: &str,
+    query: &str,
+    inputs: &[PathBuf],
+) -> std::io::Result<Option<String>> {
+    // The prefix is quoted for bash, whatever the login shell is
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(format!("{jq_bin} {prefix} -c \"$@\""))
+        .arg(jq_bin)
+        .arg(query)
+        .args(inputs.iter().map(PathBuf::as_path).map(Path::as_os_str))
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_args() {
+        <DiffOpts as clap::CommandFactory>::command().debug_assert();
+    }
+
+    /// Stands in for jq, printing the input file as it is
+    const CAT: &str = "sh -c";
+    const CAT_QUERY: &str = "cat \"$0\"";
+
+    #[test]
+    fn pinned_output_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let (input, pin) = (dir.path().join("input.json"), dir.path().join("pinned"));
+        assert_eq!(read_pinned(&pin).unwrap(), None);
+
+        std::fs::write(&input, "{\"a\": 1, \"b\": [1, 2]}\n").unwrap();
+        let run = |input: &Path| {
+            run_query(CAT, "", CAT_QUERY, &[input.to_path_buf()])
+                .unwrap()
+                .unwrap()
+        };
+        std::fs::write(&pin, run(&input)).unwrap();
+        let pinned = read_pinned(&pin).unwrap().unwrap();
+
+        let changes = diff(&parse_output(&pinned), &parse_output(&run(&input)));
+        assert_eq!(label(&changes), " identical ");
+
+        std::fs::write(&input, "{\"a\": 2, \"b\": [1]}\n").unwrap();
+        let changes = diff(&parse_output(&pinned), &parse_output(&run(&input)));
+        assert_eq!(label(&changes), " 2 differences ");
+    }
+
+    #[test]
+    fn failed_query_gives_no_output() {
+        assert_eq!(run_query(CAT, "", "exit 3", &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn label_only_for_pinned_lens() {
+        assert!(pinned_lens_active(Some("-c pinned> ")));
+        assert!(!pinned_lens_active(Some("gron> ")));
+        assert!(!pinned_lens_active(None));
+    }
+}
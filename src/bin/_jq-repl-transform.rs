@@ -1,13 +1,20 @@
 use clap::Parser;
 use jq_repl::{
     Prompt,
-    transform::{TransformConfig, transform_actions},
+    snippets::{Snippets, name_from_line},
+    state::FinalState,
+    transform::{TransformConfig, load_snippet, toggle_snippet_picker, transform_actions},
 };
+use std::path::PathBuf;
 
 /// Emit fzf actions to atomically update the prompt and preview for jq-repl.
 ///
 /// Reads the current prompt from `FZF_PROMPT`, optionally applies a flag toggle or program switch,
-/// then prints a `change-prompt(...)+change-preview(...)` action string to stdout.
+/// then prints a `change-prompt(...)+change-preview:...` action string to stdout.
+///
+/// With `--accept`, it prints `accept` instead, after recording the state for jq-repl. If the snippet
+/// picker is open, the selected snippet is loaded into the query instead. `--pick-snippet` opens or
+/// closes the picker (or says there are no snippets to pick).
 ///
 /// This is intended to be used with fzf's `transform:` action binding. Using a single `transform:`
 /// call keeps the prompt and preview update atomic — avoiding the state drift that would occur if
@@ -24,6 +31,7 @@ use jq_repl::{
 /// | `JQ_REPL_NO_COLOR_FLAG` | flag to disable color (e.g. `-M`)                         |
 /// | `JQ_REPL_GRON_CMD`      | pipe command when gron is active                          |
 /// | `JQ_REPL_BRAILLE_CMD`   | pipe command when braille is active                       |
+/// | `JQ_REPL_PREVIEW_LABEL` | command printing the preview label                        |
 #[derive(Debug, Parser)]
 #[command(name = "_jq-repl-transform", version, verbatim_doc_comment)]
 struct TransformOpts {
@@ -45,6 +53,30 @@ struct TransformOpts {
     )]
     program: Option<Option<String>>,
 
+    /// Accept the query (or load the selected snippet)
+    #[arg(long, requires = "query", conflicts_with_all = ["flag", "program"])]
+    accept: bool,
+
+    /// The current query, as given by `{q}`
+    #[arg(long, allow_hyphen_values = true)]
+    query: Option<String>,
+
+    /// The selected line in fzf's list, as given by `{}`
+    #[arg(long, allow_hyphen_values = true)]
+    selected: Option<String>,
+
+    /// Where to record the prompt and query when accepting
+    #[arg(long, requires = "accept")]
+    state_file: Option<PathBuf>,
+
+    /// Open the snippet picker, or close it if it's open
+    #[arg(long, conflicts_with_all = ["flag", "program", "accept"])]
+    pick_snippet: bool,
+
+    /// How many lines are in fzf's list, which is only non-empty while the snippet picker is open
+    #[arg(long, env = "FZF_TOTAL_COUNT", default_value_t = 0)]
+    total_count: usize,
+
     /// Input file paths to pass to jq (already shell-quoted)
     #[arg(trailing_var_arg = true)]
     input_file_paths: Vec<String>,
@@ -53,6 +85,38 @@ struct TransformOpts {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = TransformOpts::parse();
 
+    if opts.pick_snippet {
+        let picker_open = opts.total_count > 0;
+        let has_snippets = picker_open
+            || Snippets::default_path()
+                .map(|path| Snippets::load(&path))
+                .transpose()?
+                .is_some_and(|snippets| !snippets.is_empty());
+        println!("{}", toggle_snippet_picker(picker_open, has_snippets));
+        return Ok(());
+    }
+
+    if opts.accept {
+        if let Some(selected) = opts.selected.filter(|selected| !selected.is_empty())
+            && let Some(path) = Snippets::default_path()
+            && let Some(snippet) = Snippets::load(&path)?.get(name_from_line(&selected))
+        {
+            println!("{}", load_snippet(snippet));
+            return Ok(());
+        }
+
+        if let Some(state_file) = opts.state_file {
+            let state = FinalState {
+                accepted: true,
+                prompt: opts.prompt,
+                query: opts.query.unwrap_or_default(),
+            };
+            state.write(&state_file)?;
+        }
+        println!("accept");
+        return Ok(());
+    }
+
     let mut prompt = opts.prompt.parse::<Prompt>().unwrap();
     prompt.transform(opts.flag, opts.program);
 
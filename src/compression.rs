@@ -0,0 +1,158 @@
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+
+/// A compression format that input is transparently decompressed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// Detect compression from the first few bytes of the input, falling back to the extension.
+    #[must_use]
+    pub fn detect(path: &Path, header: &[u8]) -> Option<Self> {
+        Self::from_magic(header).or_else(|| Self::from_extension(path))
+    }
+
+    fn from_magic(header: &[u8]) -> Option<Self> {
+        if is_bzip2(header) {
+            return Some(Self::Bzip2);
+        }
+
+        [
+            (&[0x1f, 0x8b][..], Self::Gzip),
+            (&[0x28, 0xb5, 0x2f, 0xfd][..], Self::Zstd),
+            (&[0xfd, b'7', b'z', b'X', b'Z', 0x00][..], Self::Xz),
+        ]
+        .into_iter()
+        .find_map(|(magic, compression)| header.starts_with(magic).then_some(compression))
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        [
+            ("gz", Self::Gzip),
+            ("gzip", Self::Gzip),
+            ("zst", Self::Zstd),
+            ("zstd", Self::Zstd),
+            ("bz2", Self::Bzip2),
+            ("xz", Self::Xz),
+        ]
+        .into_iter()
+        .find_map(|(name, compression)| extension.eq_ignore_ascii_case(name).then_some(compression))
+    }
+
+    /// Wrap `reader` so that reading from it yields the decompressed bytes.
+    ///
+    /// Concatenated streams (e.g. from `cat a.gz b.gz`) are read all the way through.
+    pub fn decoder<'a>(self, reader: impl BufRead + 'a) -> std::io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+            Self::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(liblzma::bufread::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+
+    /// The path without its compression extension, so the format underneath can be detected.
+    #[must_use]
+    pub fn strip_extension(path: &Path) -> PathBuf {
+        if Self::from_extension(path).is_some() {
+            path.with_extension("")
+        } else {
+            path.to_path_buf()
+        }
+    }
+}
+
+/// `BZh` is short enough to start plain text, so the block size and the magic of the first block
+/// (or of the end of an empty stream) are checked too.
+fn is_bzip2(header: &[u8]) -> bool {
+    let [b'B', b'Z', b'h', b'1'..=b'9', block @ ..] = header else {
+        return false;
+    };
+    block.starts_with(&[0x31, 0x41, 0x59, 0x26, 0x53, 0x59])
+        || block.starts_with(&[0x17, 0x72, 0x45, 0x38, 0x50, 0x90])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn decompress(compression: Compression, compressed: &[u8]) -> String {
+        let mut output = String::new();
+        compression
+            .decoder(compressed)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn detects_magic_before_extension() {
+        let compression = Compression::detect(Path::new("events.jsonl.gz"), b"BZh91AY&SY");
+        assert_eq!(compression, Some(Compression::Bzip2));
+    }
+
+    #[test]
+    fn falls_back_to_extension() {
+        let compression = Compression::detect(Path::new("events.jsonl.zst"), b"");
+        assert_eq!(compression, Some(Compression::Zstd));
+        let compression = Compression::detect(Path::new("EVENTS.JSONL.GZ"), b"");
+        assert_eq!(compression, Some(Compression::Gzip));
+        assert_eq!(Compression::detect(Path::new("events.jsonl"), b"{}"), None);
+    }
+
+    #[test]
+    fn text_starting_with_bzh_is_not_bzip2() {
+        assert_eq!(
+            Compression::detect(Path::new("-"), b"BZh9 is a header"),
+            None
+        );
+        assert_eq!(Compression::detect(Path::new("-"), b"BZh"), None);
+
+        let mut compressed = vec![];
+        bzip2::read::BzEncoder::new(&b""[..], bzip2::Compression::fast())
+            .read_to_end(&mut compressed)
+            .unwrap();
+        let compression = Compression::detect(Path::new("-"), &compressed);
+        assert_eq!(compression, Some(Compression::Bzip2));
+    }
+
+    #[test]
+    fn strips_compression_extension() {
+        let path = Compression::strip_extension(Path::new("logs/events.jsonl.xz"));
+        assert_eq!(path, Path::new("logs/events.jsonl"));
+        let path = Compression::strip_extension(Path::new("events.json"));
+        assert_eq!(path, Path::new("events.json"));
+    }
+
+    #[test]
+    fn gzip_concatenated_members() {
+        let mut compressed = vec![];
+        for line in ["{\"a\":1}\n", "{\"a\":2}\n"] {
+            let mut encoder =
+                flate2::write::GzEncoder::new(&mut compressed, flate2::Compression::fast());
+            encoder.write_all(line.as_bytes()).unwrap();
+            encoder.finish().unwrap();
+        }
+
+        let compression = Compression::detect(Path::new("-"), &compressed);
+        assert_eq!(compression, Some(Compression::Gzip));
+        assert_eq!(
+            decompress(Compression::Gzip, &compressed),
+            "{\"a\":1}\n{\"a\":2}\n"
+        );
+    }
+
+    #[test]
+    fn zstd_round_trip() {
+        let compressed = zstd::encode_all(&b"[1,2,3]"[..], 0).unwrap();
+        assert_eq!(decompress(Compression::Zstd, &compressed), "[1,2,3]");
+    }
+}
@@ -15,12 +15,48 @@ pub struct Config {
 #[serde(default, deny_unknown_fields)]
 pub struct Keybinds {
     pub reset_lens: String,
+    /// Re-run the `--from-cmd` command and refresh the preview with its new output.
+    pub rerun_command: String,
+    /// Switch between the `--sample`/`--head` sample and the full input.
+    pub toggle_sample: String,
+    /// Save the query as a snippet, asking for its name.
+    pub save_snippet: String,
+    /// Open or close the list of snippets, to load one into the query.
+    pub pick_snippet: String,
+    /// Export the query as a script, `.jq` file or `def`, asking which.
+    pub export_query: String,
+    /// Show how the output differs between two inputs (only with exactly two).
+    pub diff_inputs: String,
+    /// Save the current output, to compare later queries against.
+    pub pin_output: String,
+    /// Show how the output differs from the pinned output.
+    pub diff_pinned: String,
+    /// Save the query, input and output as a fixture for `jq-repl test`, asking for its name.
+    pub save_fixture: String,
+    /// Copy the query to the clipboard.
+    pub copy_query: String,
+    /// Copy jq's output (without color) to the clipboard.
+    pub copy_output: String,
+    /// Copy a shell command running the query on the inputs to the clipboard.
+    pub copy_command: String,
 }
 
 impl Default for Keybinds {
     fn default() -> Self {
         Self {
             reset_lens: "alt-G".to_string(),
+            rerun_command: "alt-r".to_string(),
+            toggle_sample: "alt-s".to_string(),
+            save_snippet: "ctrl-s".to_string(),
+            pick_snippet: "ctrl-o".to_string(),
+            export_query: "alt-x".to_string(),
+            diff_inputs: "alt-D".to_string(),
+            pin_output: "alt-p".to_string(),
+            diff_pinned: "alt-P".to_string(),
+            save_fixture: "alt-F".to_string(),
+            copy_query: "alt-y".to_string(),
+            copy_output: "alt-Y".to_string(),
+            copy_command: "ctrl-alt-y".to_string(),
         }
     }
 }
@@ -45,6 +81,10 @@ pub struct External {
     /// Extra flags passed to jq before piping to the command (e.g. `["-c"]`).
     #[serde(default)]
     pub jq_flags: Vec<String>,
+    /// Pipe the input files as they were given, before any conversion to JSON, instead of jq's
+    /// output.
+    #[serde(default)]
+    pub original_input: bool,
 }
 
 impl Config {
@@ -0,0 +1,205 @@
+use serde_json::Value;
+use std::fmt::Write as _;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// A difference between two JSON values, at the path where they stop matching.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Added {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+/// Compare two values by path, rather than by the lines they're printed on.
+///
+/// Objects are compared key by key, and arrays index by index.
+#[must_use]
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_at(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let key_path = format!("{path}{}", key_segment(key));
+                match new.get(key) {
+                    Some(new_value) => diff_at(key_path, old_value, new_value, changes),
+                    None => changes.push(Change::Removed {
+                        path: key_path,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    changes.push(Change::Added {
+                        path: format!("{path}{}", key_segment(key)),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                let index_path = format!("{path}[{i}]");
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => diff_at(index_path, old, new, changes),
+                    (Some(old), None) => changes.push(Change::Removed {
+                        path: index_path,
+                        value: old.clone(),
+                    }),
+                    (None, Some(new)) => changes.push(Change::Added {
+                        path: index_path,
+                        value: new.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => changes.push(Change::Changed {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// A path segment for an object key, quoted unless it's a plain identifier.
+fn key_segment(key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!(".{key}")
+    } else {
+        format!("[{}]", Value::from(key))
+    }
+}
+
+/// Parse jq's output, which is a stream of values.
+///
+/// If it isn't all JSON, each line is parsed on its own instead, keeping lines that aren't JSON
+/// (like strings printed with `--raw-output`) as strings. A single value is returned as-is, and
+/// any other number of them as an array.
+#[must_use]
+pub fn parse_output(output: &str) -> Value {
+    let stream = serde_json::Deserializer::from_str(output)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>();
+    let mut values = stream.unwrap_or_else(|_| {
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap_or_else(|_| Value::from(line)))
+            .collect()
+    });
+
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
+
+/// Show the changes one path per line, in red and green, under a header naming both sides.
+///
+/// When there aren't any, a line saying the two are identical is shown instead.
+#[must_use]
+pub fn render(old_name: &str, new_name: &str, changes: &[Change]) -> String {
+    let mut output = format!("{BOLD}--- {old_name}\n+++ {new_name}{RESET}\n");
+    if changes.is_empty() {
+        let _ = writeln!(output, "{DIM}identical{RESET}");
+    }
+
+    for change in changes {
+        let _ = match change {
+            Change::Removed { path, value } => {
+                writeln!(output, "{RED}- {}: {value}{RESET}", display_path(path))
+            }
+            Change::Added { path, value } => {
+                writeln!(output, "{GREEN}+ {}: {value}{RESET}", display_path(path))
+            }
+            Change::Changed { path, old, new } => writeln!(
+                output,
+                "{RED}- {path}: {old}{RESET}\n{GREEN}+ {path}: {new}{RESET}",
+                path = display_path(path),
+            ),
+        };
+    }
+
+    output
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "." } else { path }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diffs_by_path() {
+        let old = json!({"name": "ada", "tags": ["a", "b"], "odd key": 1});
+        let new = json!({"name": "bob", "tags": ["a"], "extra": null, "odd key": 1});
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::Changed {
+                    path: ".name".to_string(),
+                    old: json!("ada"),
+                    new: json!("bob"),
+                },
+                Change::Removed {
+                    path: ".tags[1]".to_string(),
+                    value: json!("b"),
+                },
+                Change::Added {
+                    path: ".extra".to_string(),
+                    value: json!(null),
+                },
+            ]
+        );
+        assert_eq!(key_segment("odd key"), "[\"odd key\"]");
+    }
+
+    #[test]
+    fn parses_raw_output() {
+        assert_eq!(parse_output("{\"a\":1}\n"), json!({"a": 1}));
+        assert_eq!(parse_output("1\nplain text\n"), json!([1, "plain text"]));
+        assert_eq!(parse_output("{\n  \"a\": 1\n}\n2\n"), json!([{"a": 1}, 2]));
+    }
+
+    #[test]
+    fn renders_identical() {
+        assert_eq!(
+            render("a.json", "b.json", &[]),
+            "\x1b[1m--- a.json\n+++ b.json\x1b[0m\n\x1b[2midentical\x1b[0m\n"
+        );
+        assert!(
+            render("a.json", "b.json", &diff(&json!(1), &json!(2)))
+                .ends_with("\x1b[31m- .: 1\x1b[0m\n\x1b[32m+ .: 2\x1b[0m\n")
+        );
+    }
+}
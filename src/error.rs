@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
@@ -10,8 +11,69 @@ pub enum Error {
     Utf8(#[from] std::string::FromUtf8Error),
     #[error("{0}")]
     Fzf(std::process::ExitStatus),
+    #[error("`{command}` failed: {status}")]
+    Command {
+        command: String,
+        status: std::process::ExitStatus,
+    },
     #[error(transparent)]
     Clap(#[from] clap::Error),
     #[error(transparent)]
     Config(#[from] crate::config::ConfigError),
+    #[error("failed to watch input files: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("failed to read {}: {source}", path.display())]
+    Format {
+        path: PathBuf,
+        #[source]
+        source: crate::format::FormatError,
+    },
+    #[error("{}: no such file or directory", .0.display())]
+    MissingInput(PathBuf),
+    #[error("{}: can't be read as input", .0.display())]
+    UnreadableInput(PathBuf),
+    #[error("failed to open {}: {source}", path.display())]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Glob(#[from] glob::PatternError),
+    #[error("no files match {0}")]
+    NoMatches(String),
+    #[error("--sqlite needs jq-repl to be built with the sqlite feature")]
+    NoSqlite,
+    #[error("failed to read environment variable {name}: {source}")]
+    EnvVar {
+        name: String,
+        #[source]
+        source: std::env::VarError,
+    },
+    #[error("no saved session named {0:?}")]
+    NoSession(String),
+    #[error("failed to read session {name:?}: {source}")]
+    SessionFormat {
+        name: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("the query gave no output, so {} was left as it was", .0.display())]
+    NoOutput(PathBuf),
+    #[error("{failed} of {total} files failed")]
+    BatchFailed { failed: usize, total: usize },
+    #[error("a name is needed to save a fixture")]
+    NoFixtureName,
+    #[error("invalid fixture name {name:?}: {reason}")]
+    InvalidFixtureName { name: String, reason: String },
+    #[error("{}: not a fixture", .0.display())]
+    NoFixture(PathBuf),
+    #[error("{failed} of {total} fixtures failed")]
+    FixturesFailed { failed: usize, total: usize },
+    #[error(transparent)]
+    Export(#[from] crate::export::ExportError),
+    #[error(transparent)]
+    Snippets(#[from] crate::snippets::SnippetsError),
+    #[error("invalid JSON in {0}")]
+    InvalidJson(#[from] crate::validate::InvalidJson),
 }
@@ -0,0 +1,125 @@
+use crate::git::RevisionSpec;
+use std::path::{Path, PathBuf};
+
+/// Expand directories and glob patterns among the input paths into the files they contain.
+///
+/// Directories are searched recursively, skipping hidden files. A path is only treated as a glob
+/// pattern when nothing exists at that path, so the pattern can be quoted to keep the shell from
+/// expanding it (e.g. `'fixtures/**/*.json'`). Git revisions like `HEAD:a[1].json` are left alone.
+/// The files found for each argument are sorted, so the input order doesn't depend on the file
+/// system. Finding none is an error, for a directory as well as a pattern.
+pub fn expand(paths: &[PathBuf]) -> Result<Vec<PathBuf>, crate::Error> {
+    let mut expanded = vec![];
+
+    for path in paths {
+        let pattern = if path.is_dir() {
+            Path::new(&glob::Pattern::escape(&path.to_string_lossy()))
+                .join("**/*")
+                .to_string_lossy()
+                .into_owned()
+        } else if !path.exists() && is_glob(path) && RevisionSpec::parse(path).is_none() {
+            path.to_string_lossy().into_owned()
+        } else {
+            expanded.push(path.clone());
+            continue;
+        };
+
+        let files = matching_files(&pattern)?;
+        if files.is_empty() {
+            return Err(crate::Error::NoMatches(path.to_string_lossy().into_owned()));
+        }
+        expanded.extend(files);
+    }
+
+    Ok(expanded)
+}
+
+fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+fn matching_files(pattern: &str) -> Result<Vec<PathBuf>, crate::Error> {
+    let options = glob::MatchOptions {
+        require_literal_leading_dot: true,
+        ..glob::MatchOptions::new()
+    };
+
+    let mut files = glob::glob_with(pattern, options)?
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "b.json",
+            "a.json",
+            "nested/c.json",
+            "nested/d.yaml",
+            ".hidden.json",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{}").unwrap();
+        }
+        dir
+    }
+
+    fn relative(dir: &Path, paths: Vec<PathBuf>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|path| path.strip_prefix(dir).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn expands_directories_recursively() {
+        let dir = fixtures();
+        let expanded = expand(&[dir.path().to_path_buf()]).unwrap();
+
+        assert_eq!(
+            relative(dir.path(), expanded),
+            ["a.json", "b.json", "nested/c.json", "nested/d.yaml"]
+        );
+    }
+
+    #[test]
+    fn expands_globs() {
+        let dir = fixtures();
+        let expanded = expand(&[dir.path().join("**/*.json")]).unwrap();
+
+        assert_eq!(
+            relative(dir.path(), expanded),
+            ["a.json", "b.json", "nested/c.json"]
+        );
+    }
+
+    #[test]
+    fn keeps_other_paths_in_order() {
+        let paths = [PathBuf::from("-"), PathBuf::from("missing.json")];
+        assert_eq!(expand(&paths).unwrap(), paths);
+    }
+
+    #[test]
+    fn empty_directory_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".hidden.json"), "{}").unwrap();
+        let result = expand(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(crate::Error::NoMatches(_))));
+    }
+
+    #[test]
+    fn glob_without_matches_is_an_error() {
+        let dir = fixtures();
+        let result = expand(&[dir.path().join("*.toml")]);
+        assert!(matches!(result, Err(crate::Error::NoMatches(_))));
+    }
+}
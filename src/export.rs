@@ -0,0 +1,278 @@
+use crate::ask;
+use crate::opt::ExportArgs;
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// jq options that bind a `$name` variable, followed by the name.
+const VARIABLE_OPTIONS: [&str; 4] = ["--arg", "--argjson", "--slurpfile", "--rawfile"];
+
+/// What to turn a query into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// A bash script that runs the query on its arguments, or on the same inputs by default
+    Script,
+    /// A file to pass to `jq -f`, with a comment showing the flags it was written for
+    Jq,
+    /// A `def` appended to a module in `--jq-repl-lib`, taking jq's variables as parameters
+    Def,
+    /// A single shell command, like the one printed by `--print-command`
+    Command,
+}
+
+/// A query, along with everything needed to run it the way the preview does.
+#[derive(Debug)]
+pub struct Export<'a> {
+    pub jq_bin: &'a str,
+    /// The arguments given to jq before the query, like `-L` paths and `--arg name value`
+    pub args: &'a str,
+    pub query: &'a str,
+    /// The (already quoted) inputs that can be read again later
+    pub inputs: &'a str,
+}
+
+impl Export<'_> {
+    /// A bash script running the query on its arguments, or on the original inputs without any.
+    #[must_use]
+    pub fn script(&self) -> String {
+        let mut script = "#!/usr/bin/env bash\nset -euo pipefail\n\n".to_string();
+        if !self.inputs.is_empty() {
+            let _ = writeln!(
+                script,
+                "if [ $# -eq 0 ]; then\n    set -- {}\nfi\n",
+                self.inputs
+            );
+        }
+        let _ = writeln!(script, "exec {} \"$@\"", self.command());
+        script
+    }
+
+    /// The query by itself, for `jq -f`.
+    #[must_use]
+    pub fn jq_file(&self, path: &Path) -> String {
+        let run = [
+            self.jq_bin,
+            self.args,
+            "-f",
+            &crate::bash_quote(path),
+            self.inputs,
+        ]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+        format!("# {run}\n{}\n", self.query)
+    }
+
+    /// A function running the query, with a parameter for each of jq's variables.
+    #[must_use]
+    pub fn def(&self, name: &str) -> String {
+        let variables = variables(self.args);
+        if variables.is_empty() {
+            format!("def {name}: {};\n", self.query)
+        } else {
+            let params = variables
+                .iter()
+                .map(|variable| format!("${variable}"))
+                .collect::<Vec<_>>()
+                .join("; ");
+            format!("def {name}({params}): {};\n", self.query)
+        }
+    }
+
+    /// A shell command running the query on the original inputs.
+    #[must_use]
+    pub fn command_line(&self) -> String {
+        if self.inputs.is_empty() {
+            self.command()
+        } else {
+            format!("{} {}", self.command(), self.inputs)
+        }
+    }
+
+    fn command(&self) -> String {
+        [self.jq_bin, self.args, &crate::bash_quote(self.query)]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The names of the variables bound by jq's arguments.
+///
+/// The arguments are split on whitespace, the same way they're split when passed to jq.
+fn variables(args: &str) -> Vec<&str> {
+    let mut words = args.split_whitespace();
+    let mut variables = vec![];
+    while let Some(word) = words.next() {
+        if VARIABLE_OPTIONS.contains(&word)
+            && let Some(name) = words.next()
+        {
+            variables.push(name);
+        }
+    }
+    variables
+}
+
+/// Run `jq-repl export`, asking for the format and destination when run in a terminal without them.
+pub(crate) fn run_command(
+    args: ExportArgs,
+    export: &Export,
+    jq_repl_lib: &Path,
+) -> Result<(), ExportError> {
+    let interactive = std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+        && args.output.is_none()
+        && args.name.is_none();
+
+    let format = match args.format {
+        Some(format) => format,
+        None if interactive => {
+            let answer = ask("Export as (script, jq, def)")?;
+            clap::ValueEnum::from_str(&answer, true)
+                .map_err(|_| ExportError::UnknownFormat(answer))?
+        }
+        None => ExportFormat::Script,
+    };
+
+    if format == ExportFormat::Def {
+        let name = match args.name {
+            Some(name) => name,
+            None if interactive => ask("Name")?,
+            None => return Err(ExportError::NoName),
+        };
+        if name.is_empty() {
+            return Err(ExportError::NoName);
+        }
+
+        let module = match args.module {
+            Some(module) => module,
+            None if interactive => ask("Module (optional)")?,
+            None => String::new(),
+        };
+        let path = module_path(jq_repl_lib, &module);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        file.write_all(export.def(&name).as_bytes())?;
+        eprintln!("Added {name} to {}", path.display());
+        return Ok(());
+    }
+
+    let output = match args.output {
+        Some(output) => Some(output),
+        None if interactive => Some(PathBuf::from(ask("File")?)),
+        None => None,
+    };
+
+    let contents = |path| match format {
+        ExportFormat::Script => export.script(),
+        ExportFormat::Jq => export.jq_file(path),
+        ExportFormat::Def | ExportFormat::Command => format!("{}\n", export.command_line()),
+    };
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, contents(&output))?;
+            if format == ExportFormat::Script {
+                std::fs::set_permissions(&output, std::fs::Permissions::from_mode(0o755))?;
+            }
+            eprintln!("Wrote {}", output.display());
+        }
+        None => std::io::stdout().write_all(contents(Path::new("FILE")).as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// The file of a module in the library, or the `.jq` file loaded on startup without one.
+fn module_path(lib: &Path, module: &str) -> PathBuf {
+    let lib = match lib.strip_prefix("~") {
+        Ok(rest) => directories::BaseDirs::new()
+            .map_or_else(|| lib.to_path_buf(), |dirs| dirs.home_dir().join(rest)),
+        Err(_) => lib.to_path_buf(),
+    };
+
+    if module.is_empty() {
+        lib.join(".jq")
+    } else {
+        lib.join(format!("{module}.jq"))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("failed to export query: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unknown export format {0:?}")]
+    UnknownFormat(String),
+    #[error("a name is needed to export a def")]
+    NoName,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: Export = Export {
+        jq_bin: "gojq",
+        args: "--raw-output --arg name ada --argjson limit 3 -c",
+        query: ".[] | select(.name == $name)",
+        inputs: "'users.json'",
+    };
+
+    #[test]
+    fn script_defaults_to_inputs() {
+        assert_eq!(
+            EXPORT.script(),
+            "#!/usr/bin/env bash\nset -euo pipefail\n\nif [ $# -eq 0 ]; then\n    set -- \
+             'users.json'\nfi\n\nexec gojq --raw-output --arg name ada --argjson limit 3 -c $'.[] \
+             | select(.name == $name)' \"$@\"\n"
+        );
+    }
+
+    #[test]
+    fn command_line_reads_inputs() {
+        assert_eq!(
+            Export {
+                args: "-c",
+                ..EXPORT
+            }
+            .command_line(),
+            "gojq -c $'.[] | select(.name == $name)' 'users.json'"
+        );
+    }
+
+    #[test]
+    fn jq_file_shows_flags() {
+        assert_eq!(
+            EXPORT.jq_file(Path::new("ada.jq")),
+            "# gojq --raw-output --arg name ada --argjson limit 3 -c -f ada.jq 'users.json'\n.[] \
+             | select(.name == $name)\n"
+        );
+    }
+
+    #[test]
+    fn def_takes_variables() {
+        assert_eq!(
+            EXPORT.def("by_name"),
+            "def by_name($name; $limit): .[] | select(.name == $name);\n"
+        );
+        assert_eq!(
+            Export { args: "", ..EXPORT }.def("by_name"),
+            "def by_name: .[] | select(.name == $name);\n"
+        );
+    }
+
+    #[test]
+    fn module_defaults_to_prelude() {
+        assert_eq!(module_path(Path::new("/lib"), ""), Path::new("/lib/.jq"));
+        assert_eq!(
+            module_path(Path::new("/lib"), "users"),
+            Path::new("/lib/users.jq")
+        );
+    }
+}
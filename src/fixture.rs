@@ -0,0 +1,188 @@
+use crate::diff::{diff, parse_output, render};
+use crate::{Error, apply, bash_quote};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+const QUERY_FILE: &str = "query.jq";
+const ARGS_FILE: &str = "args";
+const INPUT_FILE: &str = "input.json";
+const EXPECTED_FILE: &str = "expected";
+
+/// A query along with its input and the output it's expected to give, to check for regressions.
+///
+/// Each fixture is a directory holding the query (`query.jq`), the jq arguments as a line of shell
+/// words (`args`), the input (`input.json`, missing for null input) and the output (`expected`).
+#[derive(Debug)]
+pub struct Fixture {
+    dir: PathBuf,
+    query: String,
+    args: String,
+    expected: Vec<u8>,
+}
+
+impl Fixture {
+    /// Record what the query outputs now, replacing any fixture with the same name.
+    ///
+    /// The inputs are combined into one, which jq reads the same way.
+    pub fn record(
+        fixture_dir: &Path,
+        name: &str,
+        jq_bin: &str,
+        args: &str,
+        query: &str,
+        inputs: &[PathBuf],
+    ) -> Result<Self, Error> {
+        std::fs::create_dir_all(fixture_dir)?;
+        let partial = tempfile::Builder::new()
+            .prefix(".partial-")
+            .tempdir_in(fixture_dir)?;
+
+        std::fs::write(partial.path().join(QUERY_FILE), format!("{query}\n"))?;
+        std::fs::write(partial.path().join(ARGS_FILE), format!("{args}\n"))?;
+        if !inputs.is_empty() {
+            let mut combined = File::create(partial.path().join(INPUT_FILE))?;
+            for input in inputs {
+                std::io::copy(&mut File::open(input)?, &mut combined)?;
+            }
+        }
+
+        let mut fixture = Self {
+            dir: partial.path().to_path_buf(),
+            query: query.to_string(),
+            args: args.to_string(),
+            expected: vec![],
+        };
+        fixture.expected = apply::output(&fixture.command(jq_bin))?;
+        std::fs::write(partial.path().join(EXPECTED_FILE), &fixture.expected)?;
+
+        // Move the old fixture aside rather than deleting it, so it's kept if the new one can't take
+        // its place. It's deleted along with `replaced` after that.
+        let dir = fixture_dir.join(name);
+        let replaced = tempfile::Builder::new()
+            .prefix(".replaced-")
+            .tempdir_in(fixture_dir)?;
+        let old = replaced.path().join(name);
+        let had_old = match std::fs::rename(&dir, &old) {
+            Ok(()) => true,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => false,
+            Err(err) => return Err(err.into()),
+        };
+        if let Err(err) = std::fs::rename(partial.path(), &dir) {
+            if had_old {
+                std::fs::rename(&old, &dir)?;
+            }
+            return Err(err.into());
+        }
+        fixture.dir = dir;
+
+        Ok(fixture)
+    }
+
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let read = |file| {
+            std::fs::read(dir.join(file)).map_err(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => Error::NoFixture(dir.to_path_buf()),
+                _ => err.into(),
+            })
+        };
+        let text = |file| -> Result<String, Error> {
+            let text = String::from_utf8(read(file)?)?;
+            Ok(text.strip_suffix('\n').unwrap_or(&text).to_string())
+        };
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            query: text(QUERY_FILE)?,
+            args: text(ARGS_FILE)?,
+            expected: read(EXPECTED_FILE)?,
+        })
+    }
+
+    /// Run the query again, returning how the output differs from what's expected, if it does.
+    pub fn check(&self, jq_bin: &str) -> Result<Option<String>, Error> {
+        let actual = apply::output(&self.command(jq_bin))?;
+        if actual == self.expected {
+            return Ok(None);
+        }
+
+        let expected = parse_output(&String::from_utf8_lossy(&self.expected));
+        let actual = parse_output(&String::from_utf8_lossy(&actual));
+        Ok(Some(render(
+            "expected",
+            "actual",
+            &diff(&expected, &actual),
+        )))
+    }
+
+    fn command(&self, jq_bin: &str) -> String {
+        let input = self.dir.join(INPUT_FILE);
+        let input = if input.exists() {
+            format!("< {}", bash_quote(input))
+        } else {
+            "< /dev/null".to_string()
+        };
+
+        [jq_bin, &self.args, &bash_quote(&self.query), &input]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The directories of all fixtures, sorted by name.
+pub fn all(fixture_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut dirs = vec![];
+    for entry in std::fs::read_dir(fixture_dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_recorded_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("data.json");
+        std::fs::write(&input, "{\"a\": [1, 2]}\n").unwrap();
+        let fixtures = dir.path().join("fixtures");
+
+        // `sh -c cat` stands in for jq, echoing its input
+        Fixture::record(&fixtures, "first", "sh -c", "", "cat", &[input]).unwrap();
+        assert_eq!(all(&fixtures).unwrap(), [fixtures.join("first")]);
+
+        let fixture = Fixture::load(&fixtures.join("first")).unwrap();
+        assert_eq!(fixture.expected, b"{\"a\": [1, 2]}\n");
+        assert_eq!(fixture.check("sh -c").unwrap(), None);
+
+        std::fs::write(fixtures.join("first").join(EXPECTED_FILE), "{\"a\": [1]}").unwrap();
+        let fixture = Fixture::load(&fixtures.join("first")).unwrap();
+        let diff = fixture.check("sh -c").unwrap().unwrap();
+        assert!(diff.contains("+ .a[1]: 2"));
+
+        // Recording again replaces the fixture, without leaving anything else behind
+        Fixture::record(&fixtures, "first", "sh -c", "", "echo 1", &[]).unwrap();
+        assert_eq!(all(&fixtures).unwrap(), [fixtures.join("first")]);
+        let fixture = Fixture::load(&fixtures.join("first")).unwrap();
+        assert_eq!(fixture.expected, b"1\n");
+    }
+
+    #[test]
+    fn missing_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            Fixture::load(&dir.path().join("nope")),
+            Err(Error::NoFixture(_))
+        ));
+    }
+}
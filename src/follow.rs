@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+/// How often new records are written to the input file and the preview refreshed.
+pub const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Keeps an input file up to date with records (lines) arriving on a stream like standard input.
+pub struct Follower {
+    /// Lines read from the stream that haven't been written to the input file yet
+    pending: Arc<Mutex<Vec<Vec<u8>>>>,
+    /// The last `window` records, when only those should be kept
+    window: Option<(usize, VecDeque<Vec<u8>>)>,
+    path: PathBuf,
+}
+
+impl Follower {
+    /// Start reading records from `source` in the background.
+    ///
+    /// The reading thread is detached, since a stream like `tail -f` may never end.
+    pub fn start(
+        source: impl BufRead + Send + 'static,
+        path: PathBuf,
+        window: Option<usize>,
+    ) -> Self {
+        let pending = Arc::new(Mutex::new(vec![]));
+
+        let sink = Arc::clone(&pending);
+        std::thread::spawn(move || {
+            for line in source.split(b'\n') {
+                let Ok(mut line) = line else { break };
+                line.push(b'\n');
+                sink.lock().expect("follow lock poisoned").push(line);
+            }
+        });
+
+        Self {
+            pending,
+            window: window.map(|size| (size, VecDeque::with_capacity(size))),
+            path,
+        }
+    }
+
+    /// Write any new records to the input file, returning whether anything changed.
+    pub fn flush(&mut self) -> std::io::Result<bool> {
+        let lines = std::mem::take(&mut *self.pending.lock().expect("follow lock poisoned"));
+        if lines.is_empty() {
+            return Ok(false);
+        }
+
+        match &mut self.window {
+            None => {
+                let mut file = OpenOptions::new().append(true).open(&self.path)?;
+                file.write_all(&lines.concat())?;
+            }
+            Some((size, records)) => {
+                records.extend(lines);
+                let excess = records.len().saturating_sub(*size);
+                records.drain(..excess);
+
+                // Replace the whole file at once, so jq never sees a partially written window
+                let dir = self.path.parent().unwrap_or_else(|| ".".as_ref());
+                let mut file = NamedTempFile::new_in(dir)?;
+                for record in &*records {
+                    file.write_all(record)?;
+                }
+                file.persist(&self.path).map_err(|err| err.error)?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flush until the file matches what's expected, giving the background thread time to read.
+    fn assert_follows(input: &'static [u8], window: Option<usize>, expected: &str) {
+        let file = NamedTempFile::new().unwrap();
+        let mut follower = Follower::start(input, file.path().to_path_buf(), window);
+
+        let mut output = String::new();
+        for _ in 0..100 {
+            follower.flush().unwrap();
+            output = std::fs::read_to_string(file.path()).unwrap();
+            if output == expected {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn appends_records() {
+        assert_follows(
+            b"{\"n\":1}\n{\"n\":2}\n{\"n\":3}",
+            None,
+            "{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n",
+        );
+    }
+
+    #[test]
+    fn keeps_last_records_in_window() {
+        assert_follows(
+            b"{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n",
+            Some(2),
+            "{\"n\":2}\n{\"n\":3}\n",
+        );
+    }
+}
@@ -0,0 +1,759 @@
+use serde_json::{Map, Value};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// The format of an input document.
+///
+/// Anything other than JSON is converted to JSON before being handed to jq, so the same jq binary,
+/// library and lenses work regardless of where the data came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// Guess the format from the file extension or its first few bytes, falling back to JSON
+    Auto,
+    /// Pass the input through unchanged
+    Json,
+    /// JSON5 or JSON with comments, normalized into strict JSON
+    #[value(alias = "jsonc")]
+    Json5,
+    /// YAML, where each document in a multi-document file becomes a separate value
+    Yaml,
+    /// TOML, with datetimes rendered as strings
+    Toml,
+    /// Comma-separated values, as an array of objects keyed by the header row
+    Csv,
+    /// Tab-separated values, as an array of objects keyed by the header row
+    Tsv,
+    /// XML, with attributes prefixed by `@` and mixed text content under `#text`
+    Xml,
+    /// MessagePack, where consecutive values become a stream
+    #[value(alias = "msgpack")]
+    Messagepack,
+    /// CBOR, where consecutive values become a stream
+    Cbor,
+    /// BSON, where consecutive documents become a stream (as relaxed extended JSON)
+    Bson,
+}
+
+/// How binary blobs in MessagePack, CBOR and BSON input are represented in JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BinaryFormat {
+    /// An array of byte values
+    #[default]
+    Array,
+    /// A base64-encoded string
+    Base64,
+}
+
+impl BinaryFormat {
+    pub(crate) fn encode(self, bytes: Vec<u8>) -> Value {
+        use base64::Engine;
+
+        match self {
+            Self::Array => bytes.into_iter().map(Value::from).collect(),
+            Self::Base64 => Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        }
+    }
+}
+
+impl InputFormat {
+    /// Resolve [`InputFormat::Auto`] into a concrete format.
+    ///
+    /// The extension is checked first, then the start of `source` is inspected without consuming
+    /// it. Anything still unrecognized is assumed to be JSON.
+    pub fn resolve(self, path: Option<&Path>, source: &mut impl BufRead) -> std::io::Result<Self> {
+        if self != Self::Auto {
+            return Ok(self);
+        }
+
+        if let Some(format) = path.and_then(Self::from_extension) {
+            return Ok(format);
+        }
+
+        Ok(Self::from_magic(source.fill_buf()?).unwrap_or(Self::Json))
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" | "jsonl" | "ndjson" => Some(Self::Json),
+            "json5" | "jsonc" => Some(Self::Json5),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "xml" => Some(Self::Xml),
+            "msgpack" | "mpk" => Some(Self::Messagepack),
+            "cbor" => Some(Self::Cbor),
+            "bson" => Some(Self::Bson),
+            _ => None,
+        }
+    }
+
+    fn from_magic(header: &[u8]) -> Option<Self> {
+        // The self-described CBOR tag (55799) is the only binary format with a signature
+        header
+            .starts_with(&[0xd9, 0xd9, 0xf7])
+            .then_some(Self::Cbor)
+    }
+
+    /// Whether input in this format can be given to jq as-is.
+    #[must_use]
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Auto | Self::Json)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid JSON5: {0}")]
+    Json5(#[from] json5::Error),
+    #[error("invalid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("invalid XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("invalid MessagePack: {0}")]
+    MessagePack(#[from] rmpv::decode::Error),
+    #[error("invalid CBOR: {0}")]
+    Cbor(#[from] ciborium::de::Error<std::io::Error>),
+    #[error("invalid BSON: {0}")]
+    Bson(#[from] bson::error::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Convert `reader` from the given format into a stream of JSON values written to `writer`.
+///
+/// Each value is written compactly on its own line.
+pub fn convert(
+    format: InputFormat,
+    binary: BinaryFormat,
+    reader: impl BufRead,
+    mut writer: impl Write,
+) -> Result<(), FormatError> {
+    let values = match format {
+        InputFormat::Auto | InputFormat::Json => {
+            let mut reader = reader;
+            std::io::copy(&mut reader, &mut writer)?;
+            return Ok(());
+        }
+        InputFormat::Json5 => from_json5(reader)?,
+        InputFormat::Yaml => from_yaml(reader)?,
+        InputFormat::Toml => vec![from_toml(reader)?],
+        InputFormat::Csv => vec![from_delimited(reader, b',')?],
+        InputFormat::Tsv => vec![from_delimited(reader, b'\t')?],
+        InputFormat::Xml => from_xml(reader)?.into_iter().collect(),
+        InputFormat::Messagepack => from_messagepack(reader, binary)?,
+        InputFormat::Cbor => from_cbor(reader, binary)?,
+        InputFormat::Bson => from_bson(reader, binary)?,
+    };
+
+    for value in values {
+        serde_json::to_writer(&mut writer, &value)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Wrap each JSON value from `reader` as `{"file": file, "value": value}`.
+///
+/// Values are copied as they were written, without being parsed completely.
+pub fn wrap_with_filename(
+    file: &str,
+    reader: impl BufRead,
+    mut writer: impl Write,
+) -> Result<(), FormatError> {
+    let file = serde_json::to_string(file)?;
+    for value in serde_json::Deserializer::from_reader(reader)
+        .into_iter::<Box<serde_json::value::RawValue>>()
+    {
+        writeln!(writer, "{{\"file\":{file},\"value\":{}}}", value?.get())?;
+    }
+
+    Ok(())
+}
+
+/// Marks the end of JSON5 input, since the parser can't tell the end of a stream apart from a
+/// value that was cut short.
+const JSON5_END: &str = "\u{0}jq-repl: end of input\u{0}";
+
+/// Parse a stream of JSON5 values, like jq does for JSON (e.g. NDJSON).
+fn from_json5(reader: impl BufRead) -> Result<Vec<Value>, FormatError> {
+    use serde::Deserialize;
+
+    let mut contents = std::io::read_to_string(reader)?;
+    contents.push_str(&format!("\n{}", Value::from(JSON5_END)));
+
+    let mut deserializer = json5::Deserializer::from_str(&contents);
+    let mut values = vec![];
+    loop {
+        let value = Value::deserialize(&mut deserializer)?;
+        if value != JSON5_END {
+            values.push(value);
+            continue;
+        }
+
+        // The marker might also have been in the input, in which case something follows it
+        match Value::deserialize(&mut deserializer) {
+            Err(err) if err.code() == Some(json5::ErrorCode::EofParsingValue) => return Ok(values),
+            next => values.extend([value, next?]),
+        }
+    }
+}
+
+fn from_yaml(reader: impl BufRead) -> Result<Vec<Value>, FormatError> {
+    use serde::Deserialize;
+
+    serde_yaml::Deserializer::from_reader(reader)
+        .map(|document| {
+            let mut value = serde_yaml::Value::deserialize(document)?;
+            value.apply_merge()?;
+            Ok(yaml_to_json(value))
+        })
+        .collect()
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    use serde_yaml::Value as Yaml;
+
+    match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                // NaN and infinity have no JSON representation, so keep YAML's spelling of them
+                n.as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map_or_else(|| Value::String(n.to_string()), Value::Number)
+            }
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(seq) => seq.into_iter().map(yaml_to_json).collect(),
+        Yaml::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (yaml_key_to_string(key), yaml_to_json(value)))
+                .collect(),
+        ),
+        Yaml::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// JSON object keys must be strings, but YAML mapping keys can be anything.
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    match yaml_to_json(key) {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+fn from_toml(reader: impl BufRead) -> Result<Value, FormatError> {
+    let contents = std::io::read_to_string(reader)?;
+    let table: toml::Table = toml::from_str(&contents)?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    use toml::Value as Toml;
+
+    match value {
+        Toml::String(s) => Value::String(s),
+        Toml::Integer(i) => Value::from(i),
+        Toml::Float(f) => float_to_json(f),
+        Toml::Boolean(b) => Value::Bool(b),
+        Toml::Datetime(datetime) => Value::String(datetime.to_string()),
+        Toml::Array(array) => array.into_iter().map(toml_to_json).collect(),
+        Toml::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_delimited(reader: impl BufRead, delimiter: u8) -> Result<Value, FormatError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader);
+    let headers = reader.headers()?.clone();
+
+    reader
+        .records()
+        .map(|record| {
+            Ok(Value::Object(
+                headers
+                    .iter()
+                    .zip(record?.iter())
+                    .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+/// An XML element that is still being read.
+struct XmlElement {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl XmlElement {
+    fn new(start: &quick_xml::events::BytesStart) -> Result<Self, FormatError> {
+        let mut fields = Map::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            let value = attribute.normalized_value(quick_xml::XmlVersion::Implicit1_0)?;
+            fields.insert(
+                format!("@{}", attribute.key.as_ref()),
+                Value::String(value.into_owned()),
+            );
+        }
+
+        Ok(Self {
+            name: start.name().as_ref().to_string(),
+            fields,
+            text: String::new(),
+        })
+    }
+
+    /// Elements with only text become strings; anything else becomes an object.
+    fn finish(self) -> (String, Value) {
+        let text = self.text.trim();
+        let value = match (self.fields.is_empty(), text.is_empty()) {
+            (true, true) => Value::Null,
+            (true, false) => Value::String(text.to_string()),
+            (false, text_is_empty) => {
+                let mut fields = self.fields;
+                if !text_is_empty {
+                    fields.insert("#text".to_string(), Value::String(text.to_string()));
+                }
+                Value::Object(fields)
+            }
+        };
+
+        (self.name, value)
+    }
+
+    /// Add a child element, collecting repeated names into an array.
+    fn push_child(&mut self, name: String, value: Value) {
+        use serde_json::map::Entry;
+
+        match self.fields.entry(name) {
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Value::Array(values) => values.push(value),
+                existing => *existing = Value::Array(vec![existing.take(), value]),
+            },
+        }
+    }
+}
+
+fn from_xml(reader: impl BufRead) -> Result<Option<Value>, FormatError> {
+    use quick_xml::escape::resolve_predefined_entity;
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    let mut buf = vec![];
+    let mut stack: Vec<XmlElement> = vec![];
+    let mut root = None;
+
+    let mut close = |stack: &mut Vec<XmlElement>, element: XmlElement| {
+        let (name, value) = element.finish();
+        match stack.last_mut() {
+            Some(parent) => parent.push_child(name, value),
+            None => root = Some(Value::Object(Map::from_iter([(name, value)]))),
+        }
+    };
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => stack.push(XmlElement::new(&start)?),
+            Event::Empty(start) => {
+                let element = XmlElement::new(&start)?;
+                close(&mut stack, element);
+            }
+            Event::End(_) => {
+                if let Some(element) = stack.pop() {
+                    close(&mut stack, element);
+                }
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.xml10_content());
+                }
+            }
+            Event::CData(cdata) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&cdata.xml10_content());
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(element) = stack.last_mut() {
+                    if let Some(ch) = reference.resolve_char_ref()? {
+                        element.text.push(ch);
+                    } else if let Some(entity) = resolve_predefined_entity(&reference) {
+                        element.text.push_str(entity);
+                    } else {
+                        // Leave unknown entities as they were written
+                        element.text.push_str(&format!("&{};", &*reference));
+                    }
+                }
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+        buf.clear();
+    }
+
+    Ok(root)
+}
+
+/// Read values one after another until the input is exhausted.
+fn read_stream<R: BufRead, E>(
+    mut reader: R,
+    mut read_value: impl FnMut(&mut R) -> Result<Value, E>,
+) -> Result<Vec<Value>, E>
+where
+    E: From<std::io::Error>,
+{
+    let mut values = vec![];
+    while !reader.fill_buf()?.is_empty() {
+        values.push(read_value(&mut reader)?);
+    }
+
+    Ok(values)
+}
+
+/// Binary formats allow non-string map keys, so encode anything else as JSON.
+fn key_to_string(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+pub(crate) fn float_to_json(f: f64) -> Value {
+    serde_json::Number::from_f64(f).map_or_else(|| Value::String(f.to_string()), Value::Number)
+}
+
+fn from_messagepack(reader: impl BufRead, binary: BinaryFormat) -> Result<Vec<Value>, FormatError> {
+    read_stream(reader, |reader| {
+        Ok(messagepack_to_json(
+            rmpv::decode::read_value(reader)?,
+            binary,
+        ))
+    })
+}
+
+fn messagepack_to_json(value: rmpv::Value, binary: BinaryFormat) -> Value {
+    use rmpv::Value as MessagePack;
+
+    match value {
+        MessagePack::Nil => Value::Null,
+        MessagePack::Boolean(b) => Value::Bool(b),
+        MessagePack::Integer(i) => i
+            .as_i64()
+            .map(Value::from)
+            .or_else(|| i.as_u64().map(Value::from))
+            .unwrap_or_else(|| float_to_json(i.as_f64().unwrap_or(f64::NAN))),
+        MessagePack::F32(f) => float_to_json(f64::from(f)),
+        MessagePack::F64(f) => float_to_json(f),
+        MessagePack::String(s) if s.is_str() => Value::String(s.into_str().unwrap_or_default()),
+        // Invalid UTF-8 is kept as bytes rather than failing the whole document
+        MessagePack::String(s) => binary.encode(s.into_bytes()),
+        MessagePack::Binary(bytes) => binary.encode(bytes),
+        MessagePack::Array(values) => values
+            .into_iter()
+            .map(|value| messagepack_to_json(value, binary))
+            .collect(),
+        MessagePack::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key_to_string(messagepack_to_json(key, binary)),
+                        messagepack_to_json(value, binary),
+                    )
+                })
+                .collect(),
+        ),
+        MessagePack::Ext(kind, data) => serde_json::json!({
+            "type": kind,
+            "data": binary.encode(data),
+        }),
+    }
+}
+
+fn from_cbor(reader: impl BufRead, binary: BinaryFormat) -> Result<Vec<Value>, FormatError> {
+    read_stream(reader, |reader| {
+        let value: ciborium::Value = ciborium::from_reader(reader)?;
+        Ok(cbor_to_json(value, binary))
+    })
+}
+
+fn cbor_to_json(value: ciborium::Value, binary: BinaryFormat) -> Value {
+    use ciborium::Value as Cbor;
+
+    match value {
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            i64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(i).map(Value::from))
+                .unwrap_or_else(|_| Value::String(i.to_string()))
+        }
+        Cbor::Bytes(bytes) => binary.encode(bytes),
+        Cbor::Float(f) => float_to_json(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Null => Value::Null,
+        // Tags only hint at how to interpret the value (e.g. as a date), so keep the value itself
+        Cbor::Tag(_, value) => cbor_to_json(*value, binary),
+        Cbor::Array(values) => values
+            .into_iter()
+            .map(|value| cbor_to_json(value, binary))
+            .collect(),
+        Cbor::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key_to_string(cbor_to_json(key, binary)),
+                        cbor_to_json(value, binary),
+                    )
+                })
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+fn from_bson(reader: impl BufRead, binary: BinaryFormat) -> Result<Vec<Value>, FormatError> {
+    read_stream(reader, |reader| {
+        let document = bson::Document::from_reader(reader)?;
+        Ok(bson_to_json(bson::Bson::Document(document), binary))
+    })
+}
+
+fn bson_to_json(value: bson::Bson, binary: BinaryFormat) -> Value {
+    use bson::Bson;
+
+    match value {
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, bson_to_json(value, binary)))
+                .collect(),
+        ),
+        Bson::Array(values) => values
+            .into_iter()
+            .map(|value| bson_to_json(value, binary))
+            .collect(),
+        Bson::Binary(blob) => binary.encode(blob.bytes),
+        other => other.into_relaxed_extjson(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert_to_string(format: InputFormat, input: impl AsRef<[u8]>) -> String {
+        convert_binary_to_string(format, BinaryFormat::Array, input)
+    }
+
+    fn convert_binary_to_string(
+        format: InputFormat,
+        binary: BinaryFormat,
+        input: impl AsRef<[u8]>,
+    ) -> String {
+        let mut output = vec![];
+        convert(format, binary, input.as_ref(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn resolve(format: InputFormat, path: Option<&str>, header: &[u8]) -> InputFormat {
+        let mut source = header;
+        format.resolve(path.map(Path::new), &mut source).unwrap()
+    }
+
+    #[test]
+    fn auto_uses_extension() {
+        let format = resolve(InputFormat::Auto, Some("config.YML"), b"");
+        assert_eq!(format, InputFormat::Yaml);
+    }
+
+    #[test]
+    fn auto_falls_back_to_json() {
+        assert_eq!(resolve(InputFormat::Auto, None, b"{}"), InputFormat::Json);
+        let format = resolve(InputFormat::Auto, Some("data.txt"), b"[]");
+        assert_eq!(format, InputFormat::Json);
+    }
+
+    #[test]
+    fn auto_detects_self_described_cbor() {
+        let format = resolve(InputFormat::Auto, None, &[0xd9, 0xd9, 0xf7, 0x01]);
+        assert_eq!(format, InputFormat::Cbor);
+    }
+
+    #[test]
+    fn explicit_format_ignores_extension() {
+        let format = resolve(InputFormat::Toml, Some("data.json"), b"");
+        assert_eq!(format, InputFormat::Toml);
+    }
+
+    #[test]
+    fn auto_detects_jsonc() {
+        let format = resolve(InputFormat::Auto, Some(".vscode/settings.jsonc"), b"");
+        assert_eq!(format, InputFormat::Json5);
+    }
+
+    #[test]
+    fn json5_comments_and_trailing_commas() {
+        let output = convert_to_string(
+            InputFormat::Json5,
+            "// settings\n{ compilerOptions: { strict: true, /* inline */ }, 'paths': [1,], }",
+        );
+        assert_eq!(
+            output,
+            "{\"compilerOptions\":{\"strict\":true},\"paths\":[1]}\n"
+        );
+    }
+
+    #[test]
+    fn json5_stream() {
+        let output = convert_to_string(InputFormat::Json5, "{a: 1,}\n// next\n{'b': [2,],}\n");
+        assert_eq!(output, "{\"a\":1}\n{\"b\":[2]}\n");
+
+        let mut output = vec![];
+        let truncated = convert(
+            InputFormat::Json5,
+            BinaryFormat::Array,
+            "{a: 1}\n{b:".as_bytes(),
+            &mut output,
+        );
+        assert!(matches!(truncated, Err(FormatError::Json5(_))));
+    }
+
+    #[test]
+    fn yaml_multi_document_is_a_stream() {
+        let output = convert_to_string(InputFormat::Yaml, "a: 1\n---\n- b\n- 2\n");
+        assert_eq!(output, "{\"a\":1}\n[\"b\",2]\n");
+    }
+
+    #[test]
+    fn yaml_non_string_keys() {
+        let output = convert_to_string(InputFormat::Yaml, "1: one\ntrue: yes\n");
+        assert_eq!(output, "{\"1\":\"one\",\"true\":\"yes\"}\n");
+    }
+
+    #[test]
+    fn toml_datetime_is_string() {
+        let output = convert_to_string(InputFormat::Toml, "[a]\nb = 1979-05-27T07:32:00Z\n");
+        assert_eq!(output, "{\"a\":{\"b\":\"1979-05-27T07:32:00Z\"}}\n");
+    }
+
+    #[test]
+    fn csv_rows_are_objects() {
+        let output = convert_to_string(InputFormat::Csv, "name,age\nada,36\n");
+        assert_eq!(output, "[{\"name\":\"ada\",\"age\":\"36\"}]\n");
+    }
+
+    #[test]
+    fn tsv_rows_are_objects() {
+        let output = convert_to_string(InputFormat::Tsv, "name\tage\nada\t36\n");
+        assert_eq!(output, "[{\"name\":\"ada\",\"age\":\"36\"}]\n");
+    }
+
+    #[test]
+    fn xml_attributes_text_and_repeated_children() {
+        let output = convert_to_string(
+            InputFormat::Xml,
+            r#"<?xml version="1.0"?>
+            <fruit kind="tree"><name>apple &amp; pear</name><seed/><seed>2</seed>ripe</fruit>"#,
+        );
+        assert_eq!(
+            output,
+            "{\"fruit\":{\"@kind\":\"tree\",\"name\":\"apple & pear\",\"seed\":[null,\"2\"],\"#text\":\"ripe\"}}\n"
+        );
+    }
+
+    #[test]
+    fn messagepack_stream() {
+        // {"a": [1, -1]} followed by true
+        let input = [0x81, 0xa1, b'a', 0x92, 0x01, 0xff, 0xc3];
+        let output = convert_to_string(InputFormat::Messagepack, input);
+        assert_eq!(output, "{\"a\":[1,-1]}\ntrue\n");
+    }
+
+    #[test]
+    fn messagepack_binary_as_base64() {
+        let input = [0xc4, 0x03, b'a', b'b', b'c'];
+        let output =
+            convert_binary_to_string(InputFormat::Messagepack, BinaryFormat::Base64, input);
+        assert_eq!(output, "\"YWJj\"\n");
+    }
+
+    #[test]
+    fn cbor_bytes_as_array() {
+        // Self-described tag wrapping {"k": h'0102'}
+        let input = [0xd9, 0xd9, 0xf7, 0xa1, 0x61, b'k', 0x42, 0x01, 0x02];
+        let output = convert_to_string(InputFormat::Cbor, input);
+        assert_eq!(output, "{\"k\":[1,2]}\n");
+    }
+
+    #[test]
+    fn bson_documents_are_a_stream() {
+        let mut input = vec![];
+        for n in [1, 2] {
+            bson::doc! { "n": n, "blob": bson::Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: vec![0xff] } }
+                .to_writer(&mut input)
+                .unwrap();
+        }
+        let output = convert_binary_to_string(InputFormat::Bson, BinaryFormat::Base64, input);
+        assert_eq!(
+            output,
+            "{\"n\":1,\"blob\":\"/w==\"}\n{\"n\":2,\"blob\":\"/w==\"}\n"
+        );
+    }
+
+    #[test]
+    fn undecodable_bytes_are_an_error() {
+        let mut output = vec![];
+        let result = convert(
+            InputFormat::Messagepack,
+            BinaryFormat::Array,
+            &[0x92, 0x01][..],
+            &mut output,
+        );
+        assert!(matches!(result, Err(FormatError::MessagePack(_))));
+    }
+
+    #[test]
+    fn wraps_values_with_filename() {
+        let mut output = vec![];
+        wrap_with_filename("a \"b\".json", &b"{\"n\": 1}\n[2]"[..], &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"file\":\"a \\\"b\\\".json\",\"value\":{\"n\": 1}}\n{\"file\":\"a \\\"b\\\".json\",\"value\":[2]}\n"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::io::BufReader;
+use std::path::Path;
+use std::process::{ChildStdout, Command, Stdio};
+
+/// A file as it was at some git revision, written as `REV:path` (e.g. `HEAD~3:fixtures/a.json`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RevisionSpec<'a> {
+    pub revision: &'a str,
+    pub path: &'a str,
+}
+
+impl<'a> RevisionSpec<'a> {
+    /// Recognize an input path as a revision spec.
+    ///
+    /// Anything that exists on disk is taken to be a regular file instead, even if it has a colon.
+    /// So is anything whose revision isn't known to git here, so a mistyped path is still reported
+    /// as missing.
+    #[must_use]
+    pub fn parse(input: &'a Path) -> Option<Self> {
+        if input.exists() {
+            return None;
+        }
+
+        Self::split(input).filter(Self::resolves)
+    }
+
+    fn split(input: &'a Path) -> Option<Self> {
+        let (revision, path) = input.to_str()?.split_once(':')?;
+        (!revision.is_empty() && !path.is_empty()).then_some(Self { revision, path })
+    }
+
+    /// Whether the revision names something in the repository of the current directory.
+    fn resolves(&self) -> bool {
+        Command::new("git")
+            .args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                "--end-of-options",
+                self.revision,
+            ])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Read the file at the revision with `git show`, passing its output to `read` as it comes in.
+    ///
+    /// The path is resolved the same way as with `git show`: relative to the root of the
+    /// repository, unless it starts with `./` or `../`.
+    pub fn show<T>(
+        &self,
+        read: impl FnOnce(BufReader<ChildStdout>) -> Result<T, crate::Error>,
+    ) -> Result<T, crate::Error> {
+        let mut child = Command::new("git")
+            .arg("show")
+            .arg(self.to_string())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let output = read(BufReader::new(
+            child.stdout.take().expect("stdout is piped"),
+        ));
+
+        // What git says went wrong explains more than whatever was read before it stopped
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(crate::Error::Command {
+                command: format!("git show {self}"),
+                status,
+            });
+        }
+        output
+    }
+}
+
+impl std::fmt::Display for RevisionSpec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.revision, self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_revision_and_path() {
+        let spec = RevisionSpec::split(Path::new("origin/main:fixtures/a.json"));
+        assert_eq!(
+            spec,
+            Some(RevisionSpec {
+                revision: "origin/main",
+                path: "fixtures/a.json"
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_plain_and_existing_paths() {
+        assert_eq!(RevisionSpec::parse(Path::new("fixtures/a.json")), None);
+        assert_eq!(RevisionSpec::split(Path::new(":a.json")), None);
+        assert_eq!(
+            RevisionSpec::parse(Path::new("no-such-revision:a.json")),
+            None
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("HEAD:a.json");
+        std::fs::write(&existing, "{}").unwrap();
+        assert_eq!(RevisionSpec::parse(&existing), None);
+    }
+}
@@ -1,19 +1,49 @@
+mod apply;
+mod compression;
 pub mod config;
+pub mod diff;
 mod error;
+mod expand;
+mod export;
+mod fixture;
+mod follow;
+mod format;
+mod git;
+mod listen;
 mod opt;
 mod prompt;
+mod sample;
+mod session;
+pub mod snippets;
+mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod state;
 pub mod transform;
+mod validate;
+mod watch;
 
 use clap::Parser;
+use compression::Compression;
 pub use error::Error;
-use opt::Opt;
+use follow::Follower;
+use format::InputFormat;
+use git::RevisionSpec;
+use listen::FzfRemote;
+use opt::{Opt, SubCommand};
 pub use prompt::Prompt;
+use sample::{Sample, SampleMode};
+use session::Session;
 use shell_quote::{Bash, Quote};
+use source::{Progress, SourceKind};
+use state::FinalState;
 use std::fs::File;
-use std::io::IsTerminal;
-use std::path::{Path, PathBuf};
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::mpsc::RecvTimeoutError;
 use tempfile::NamedTempFile;
+use transform::{DEFAULT_PREVIEW_WINDOW, DIFF_PROGRAM, PINNED_PROGRAM};
 
 pub use config::Config;
 
@@ -53,6 +83,75 @@ pub fn run() -> Result<(), Error> {
         return Ok(());
     }
 
+    match opt.command.take() {
+        Some(SubCommand::Snippets(command)) => {
+            snippets::run_command(command)?;
+            return Ok(());
+        }
+        Some(SubCommand::Export(args)) => {
+            let inputs = std::env::var("JQ_REPL_INPUT_FILES").unwrap_or_default();
+            let query = args.query.clone();
+            let (jq_bin, jq_args) = preview_jq_args(&opt, args.prompt.as_deref());
+
+            let export = export::Export {
+                jq_bin: &jq_bin,
+                args: &jq_args,
+                query: &query,
+                inputs: &inputs,
+            };
+            export::run_command(args, &export, &opt.jq_repl_lib)?;
+            return Ok(());
+        }
+        Some(SubCommand::SaveFixture {
+            name,
+            prompt,
+            query,
+            inputs,
+        }) => {
+            let name = match name {
+                Some(name) => name,
+                None if std::io::stdin().is_terminal() => ask("Fixture name")?,
+                None => return Err(Error::NoFixtureName),
+            };
+            if name.is_empty() {
+                return Err(Error::NoFixtureName);
+            }
+            let name = session::parse_name(&name)
+                .map_err(|reason| Error::InvalidFixtureName { name, reason })?;
+
+            let (jq_bin, jq_args) = preview_jq_args(&opt, prompt.as_deref());
+            fixture::Fixture::record(&opt.fixture_dir, &name, &jq_bin, &jq_args, &query, &inputs)?;
+            eprintln!("Saved {}", opt.fixture_dir.join(name).display());
+            return Ok(());
+        }
+        Some(SubCommand::Test { names }) => return run_fixtures(&opt, &names),
+        Some(SubCommand::Apply(args)) => return run_apply(opt, &args),
+        None => {}
+    }
+
+    if opt.list_sessions {
+        for name in session::list()? {
+            match Session::load(&name) {
+                Ok(session) => println!("{name}\t{}", session.query),
+                Err(_) => println!("{name}"),
+            }
+        }
+        return Ok(());
+    }
+
+    let resumed = opt.resume.as_deref().map(Session::load).transpose()?;
+    if let Some(session) = &resumed {
+        opt.files.clone_from(&session.inputs);
+        opt.raw_input |= session.raw_input;
+        opt.null_input |= session.null_input;
+        if opt.jq_args.is_empty() {
+            opt.jq_args.clone_from(&session.jq_args);
+        }
+    }
+
+    // Saved before the input flags are added, which are restored separately
+    let jq_args = opt.jq_args.clone();
+
     let config = if opt.clean {
         Config::default()
     } else {
@@ -84,17 +183,40 @@ pub fn run() -> Result<(), Error> {
         std::fs::create_dir_all(parent)?;
     }
 
-    opt.null_input = opt.null_input || (std::io::stdin().is_terminal() && opt.files.is_empty());
-    if opt.null_input {
-        opt.jq_args.push(opt.null_input_flag.clone());
+    opt.files = expand::expand(&opt.files)?;
+
+    opt.null_input = opt.null_input
+        || (std::io::stdin().is_terminal()
+            && opt.files.is_empty()
+            && opt.json.is_empty()
+            && opt.json_env.is_empty()
+            && opt.sqlite.is_none()
+            && opt.from_cmd.is_none());
+    push_input_flags(&mut opt);
+
+    let mut files = get_files(&opt)?;
+    let apply_to_files = apply::unique(expand::expand(&opt.apply_to)?);
+
+    // Wrapped values can still be told apart once their inputs are combined into one
+    if files.len() > 1 && opt.pass_as_stdin && opt.with_filename {
+        if opt.watch {
+            let err = <Opt as clap::CommandFactory>::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "can't watch several files combined for --pass-as-stdin",
+            );
+            return Err(Error::from(err));
+        }
+        files = vec![combine_inputs(&files)?];
     }
 
-    if opt.raw_input {
-        opt.jq_args.push(opt.raw_input_flag.clone());
+    if opt.write_back && !matches!(files.as_slice(), [InputFile::File(_)]) {
+        let err = <Opt as clap::CommandFactory>::command().error(
+            clap::error::ErrorKind::ArgumentConflict,
+            "--write-back needs a single JSON file to replace, not standard input or converted input",
+        );
+        return Err(Error::from(err));
     }
 
-    let files = get_files(&opt.files)?;
-
     if files.len() > 1 && opt.pass_as_stdin {
         let err = <Opt as clap::CommandFactory>::command().error(
             clap::error::ErrorKind::ArgumentConflict,
@@ -103,25 +225,164 @@ pub fn run() -> Result<(), Error> {
         return Err(Error::from(err));
     }
 
-    let input_files = files
-        .iter()
-        .map(std::string::ToString::to_string)
-        .collect::<Vec<_>>();
-    let input_file_paths = if opt.pass_as_stdin {
-        format!("< {}", &input_files[0])
-    } else {
-        input_files.join(" ")
-    };
+    // Private to this session, for sockets and other files shared with fzf
+    let runtime_dir = tempfile::Builder::new().prefix("jq-repl-").tempdir()?;
+
+    let sample_mode = opt
+        .sample
+        .map(SampleMode::Random)
+        .or(opt.head.map(SampleMode::Head));
+    let sample = sample_mode
+        .map(|mode| {
+            let inputs = files.iter().map(InputFile::path).collect::<Vec<_>>();
+            Sample::create(runtime_dir.path(), &inputs, mode, opt.raw_input)
+        })
+        .transpose()?;
+
+    let save_session = opt.save_session.as_ref().or(opt.resume.as_ref());
+    let state_file = runtime_dir.path().join("state");
+    let prints_accepted = opt.print_result || opt.print_query || opt.print_command;
+    let records_state =
+        save_session.is_some() || prints_accepted || opt.write_back || !opt.apply_to.is_empty();
 
     // Keep a reference to the temp file alive until we quit
-    let mut fzf_cmd = build_fzf_cmd(&opt, &config, history_file.as_deref(), &input_file_paths)?;
+    let mut fzf_cmd = build_fzf_cmd(
+        &opt,
+        &config,
+        history_file.as_deref(),
+        &files,
+        &Runtime {
+            sample: sample.as_ref(),
+            resumed: resumed.as_ref(),
+            state_file: records_state.then_some(state_file.as_path()),
+            pin_file: &runtime_dir.path().join("pinned"),
+        },
+    )?;
+
+    // Keep stdout clean for what's printed, since fzf draws on the terminal either way
+    if prints_accepted {
+        fzf_cmd.stdout(Stdio::null());
+    }
+
+    let command_output = files
+        .iter()
+        .enumerate()
+        .find_map(|(i, file)| file.command_output().map(|output| (i, output)));
+
+    let remote = FzfRemote::new(runtime_dir.path());
+    if opt.watch || opt.follow || command_output.is_some() {
+        fzf_cmd.arg(remote.listen_arg());
+    }
 
     if opt.show_fzf_command {
         print_fzf_command(&fzf_cmd);
         return Ok(());
     }
 
-    let status = fzf_cmd.spawn()?.wait()?;
+    let status = std::thread::scope(|scope| -> Result<_, Error> {
+        let watcher = if opt.watch {
+            let watched = watched_files(&files);
+            let (watcher, changes) =
+                watch::watch(watched.iter().map(|(_, file_name)| file_name.to_path_buf()))?;
+
+            let (files, remote, opt) = (&files, &remote, &opt);
+            scope.spawn(move || {
+                for changed in changes {
+                    for (i, file_name) in changed.into_iter().map(|i| watched[i]) {
+                        // A half-written file is expected to be followed by another change, so
+                        // keep showing the last good version until then
+                        let _ = read_file(opt, file_name)
+                            .and_then(|fresh| files[i].replace_with(fresh));
+                    }
+                    let _ = remote.send("refresh-preview");
+                }
+            });
+
+            Some(watcher)
+        } else {
+            None
+        };
+
+        // Re-running the command only replaces its raw output, which is then read again here
+        let command_watcher = if let Some((i, output)) = command_output {
+            let (watcher, changes) = watch::watch([output.to_path_buf()])?;
+
+            let (files, remote, opt) = (&files, &remote, &opt);
+            scope.spawn(move || {
+                for _ in changes {
+                    let _ = read_command_output(opt, output)
+                        .and_then(|fresh| files[i].replace_with(fresh));
+                    let _ = remote.send("refresh-preview");
+                }
+            });
+
+            Some(watcher)
+        } else {
+            None
+        };
+
+        let (stop_following, follow_ticks) = std::sync::mpsc::channel::<()>();
+        if let Some(InputFile::Follow(file)) = files
+            .iter()
+            .find(|file| matches!(file, InputFile::Follow(_)))
+        {
+            let mut follower = Follower::start(
+                BufReader::new(std::io::stdin()),
+                file.path().to_path_buf(),
+                opt.follow_window,
+            );
+            let remote = &remote;
+            scope.spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) =
+                    follow_ticks.recv_timeout(follow::REFRESH_INTERVAL)
+                {
+                    if follower.flush().unwrap_or(false) {
+                        let _ = remote.send("refresh-preview");
+                    }
+                }
+            });
+        }
+
+        let status = fzf_cmd.spawn()?.wait()?;
+        drop(watcher);
+        drop(command_watcher);
+        drop(stop_following);
+        Ok(status)
+    })?;
+
+    let final_state = FinalState::read(&state_file)?;
+
+    if let Some(name) = save_session {
+        let mut session = resumed.unwrap_or_else(|| Session {
+            query: ".".to_string(),
+            prompt: Prompt::new(opt.raw_input, opt.null_input).to_string(),
+            raw_input: opt.raw_input,
+            null_input: opt.null_input,
+            jq_args: vec![],
+            inputs: vec![],
+        });
+        session.jq_args = jq_args;
+        if let Some(state) = &final_state {
+            session.update(state);
+        }
+        session.save(name, &files)?;
+    }
+
+    if let Some(state) = final_state.filter(|state| state.accepted) {
+        if opt.write_back {
+            write_back(&opt, &files, &state)?;
+        } else if let Some(destination) = opt.destination.get()
+            && !apply_to_files.is_empty()
+        {
+            let prompt = state
+                .prompt
+                .parse::<Prompt>()
+                .unwrap_or_else(|()| Prompt::new(opt.raw_input, opt.null_input));
+            apply_to(&opt, &prompt, &state.query, &apply_to_files, destination)?;
+        } else {
+            print_accepted(&opt, &config, &files, &state)?;
+        }
+    }
 
     // Forward the return status from fzf. An error code of 1 means no match was found,
     // which is meaningless here.
@@ -132,6 +393,228 @@ pub fn run() -> Result<(), Error> {
     }
 }
 
+/// Print whatever was asked for with `--print-result`, `--print-query` or `--print-command`.
+fn print_accepted(
+    opt: &Opt,
+    config: &Config,
+    files: &[InputFile],
+    state: &FinalState,
+) -> Result<(), Error> {
+    let prompt = state
+        .prompt
+        .parse::<Prompt>()
+        .unwrap_or_else(|()| Prompt::new(opt.raw_input, opt.null_input));
+
+    if opt.print_result {
+        let input_files = files.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let command = jq_command(
+            opt,
+            &prompt,
+            &state.query,
+            &opt.no_color_flag,
+            &input_file_paths(opt, &input_files),
+        );
+        // Quoted for bash, whatever the login shell is
+        let status = Command::new("bash").arg("-c").arg(&command).status()?;
+        if !status.success() {
+            return Err(Error::Command { command, status });
+        }
+    } else if opt.print_query {
+        println!("{}", state.query);
+    } else if opt.print_command {
+        println!(
+            "{}",
+            command_line(opt, config, files, &prompt, &state.query)
+        );
+    }
+
+    Ok(())
+}
+
+/// A command line giving the same output as the preview, through the active lens, for
+/// `--print-command`.
+fn command_line(
+    opt: &Opt,
+    config: &Config,
+    files: &[InputFile],
+    prompt: &Prompt,
+    query: &str,
+) -> String {
+    let input_files = lasting_input_files(files);
+    let input_file_paths = if input_files.is_empty() {
+        String::new()
+    } else {
+        input_file_paths(opt, &input_files)
+    };
+
+    let mut command = jq_command(opt, prompt, query, "", &input_file_paths);
+    if let Some(lens) = prompt.program().and_then(|name| config.lens.get(name)) {
+        command.push_str(" | ");
+        command.push_str(&lens.command);
+    }
+    command
+}
+
+/// Replace the only input file with the output of the accepted query.
+fn write_back(opt: &Opt, files: &[InputFile], state: &FinalState) -> Result<(), Error> {
+    let [InputFile::File(path)] = files else {
+        unreachable!("--write-back is checked for a single file before starting fzf");
+    };
+    let prompt = state
+        .prompt
+        .parse::<Prompt>()
+        .unwrap_or_else(|()| Prompt::new(opt.raw_input, opt.null_input));
+
+    let command = jq_command(
+        opt,
+        &prompt,
+        &state.query,
+        &opt.no_color_flag,
+        &input_file_paths(opt, &[bash_quote(path)]),
+    );
+    let output = apply::output(&command)?;
+    if output.is_empty() {
+        return Err(Error::NoOutput(path.to_path_buf()));
+    }
+
+    let backup = apply::replace_with_backup(path, &output)?;
+    eprintln!(
+        "Wrote {} (the original is in {})",
+        path.display(),
+        backup.display()
+    );
+    Ok(())
+}
+
+/// The jq binary and arguments the preview uses, including the runtime flags in the prompt.
+///
+/// These are passed down from jq-repl when run inside it, or else taken from the command line.
+fn preview_jq_args(opt: &Opt, prompt: Option<&str>) -> (String, String) {
+    let jq_bin = std::env::var("JQ_REPL_JQ_BIN").unwrap_or_else(|_| opt.jq_bin.clone());
+    let prefix = std::env::var("JQ_REPL_JQ_ARG_PREFIX").unwrap_or_else(|_| get_jq_arg_prefix(opt));
+    let prompt = prompt
+        .and_then(|prompt| prompt.parse::<Prompt>().ok())
+        .unwrap_or_else(|| Prompt::new(opt.raw_input, opt.null_input));
+
+    let jq_args = [prefix.trim(), &prompt.jq_flags()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (jq_bin, jq_args)
+}
+
+/// Check every fixture (or the named ones), showing how the output changed for those that fail.
+fn run_fixtures(opt: &Opt, names: &[String]) -> Result<(), Error> {
+    let dirs = if names.is_empty() {
+        fixture::all(&opt.fixture_dir)?
+    } else {
+        names
+            .iter()
+            .map(|name| opt.fixture_dir.join(name))
+            .collect()
+    };
+
+    let mut failed = 0;
+    for dir in &dirs {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        match fixture::Fixture::load(dir).and_then(|fixture| fixture.check(&opt.jq_bin)) {
+            Ok(None) => println!("ok      {name}"),
+            Ok(Some(diff)) => {
+                println!("FAILED  {name}\n{diff}");
+                failed += 1;
+            }
+            Err(err) => {
+                println!("FAILED  {name}: {err}");
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {failed} failed", dirs.len() - failed);
+    if failed > 0 {
+        return Err(Error::FixturesFailed {
+            failed,
+            total: dirs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Pass the input flags on to jq, so it reads the input the way the preview does.
+fn push_input_flags(opt: &mut Opt) {
+    if opt.null_input {
+        opt.jq_args.push(opt.null_input_flag.clone());
+    }
+
+    if opt.raw_input {
+        opt.jq_args.push(opt.raw_input_flag.clone());
+    }
+}
+
+/// `jq-repl apply`, running a query on files without the interface.
+fn run_apply(mut opt: Opt, args: &opt::ApplyArgs) -> Result<(), Error> {
+    let Some(destination) = args.destination.get() else {
+        let err = <Opt as clap::CommandFactory>::command().error(
+            clap::error::ErrorKind::MissingRequiredArgument,
+            "either --output-dir or --in-place is needed",
+        );
+        return Err(Error::from(err));
+    };
+    opt.jq_args.clone_from(&args.jq_args);
+    push_input_flags(&mut opt);
+    let prompt = Prompt::new(opt.raw_input, opt.null_input);
+    let files = apply::unique(expand::expand(&args.files)?);
+    apply_to(&opt, &prompt, &args.query, &files, destination)
+}
+
+/// Run the query on each of the files separately, and report how it went.
+fn apply_to(
+    opt: &Opt,
+    prompt: &Prompt,
+    query: &str,
+    files: &[std::path::PathBuf],
+    destination: apply::Destination,
+) -> Result<(), Error> {
+    let summary = apply::batch(files, destination, |file| {
+        let input = input_file_paths(opt, &[bash_quote(file)]);
+        jq_command(opt, prompt, query, &opt.no_color_flag, &input)
+    });
+
+    if summary.failed > 0 {
+        return Err(Error::BatchFailed {
+            failed: summary.failed,
+            total: summary.total,
+        });
+    }
+    let plural = if summary.total == 1 { "" } else { "s" };
+    eprintln!("Ran the query on {} file{plural}", summary.total);
+    Ok(())
+}
+
+/// A shell command running the query the same way the preview does, with the runtime flags from
+/// the prompt.
+fn jq_command(
+    opt: &Opt,
+    prompt: &Prompt,
+    query: &str,
+    color_flag: &str,
+    input_file_paths: &str,
+) -> String {
+    [
+        opt.jq_bin.as_str(),
+        get_jq_arg_prefix(opt).trim(),
+        color_flag,
+        &prompt.jq_flags(),
+        &bash_quote(query),
+        input_file_paths,
+    ]
+    .into_iter()
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
 fn print_fzf_command(fzf_cmd: &Command) {
     println!("#!/bin/bash");
     println!();
@@ -205,6 +688,8 @@ fn print_verbose_versions(opt: &Opt) -> Result<(), Error> {
     }
 
     print_cmd_version(&opt.transform_bin, "--version")?;
+    print_cmd_version(&opt.diff_bin, "--version")?;
+    print_cmd_version(&opt.clipboard_bin, "--version")?;
 
     Ok(())
 }
@@ -224,37 +709,147 @@ pub fn bash_quote_join<T: AsRef<std::ffi::OsStr>>(args: impl IntoIterator<Item =
 pub enum InputFile<'a> {
     Stdin(NamedTempFile),
     File(&'a Path),
+    /// Output captured from `--from-cmd`, replaced whenever the command is re-run
+    Command(NamedTempFile),
+    /// Standard input in `--follow` mode, which keeps growing in the background
+    Follow(NamedTempFile),
+    /// Input converted to JSON, along with where it came from
+    Converted {
+        original: Box<InputFile<'a>>,
+        json: NamedTempFile,
+    },
 }
 
-impl std::fmt::Display for InputFile<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let path = match self {
-            Self::Stdin(file) => file.path(),
+impl<'a> InputFile<'a> {
+    /// The path jq reads from.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Stdin(file)
+            | Self::Command(file)
+            | Self::Follow(file)
+            | Self::Converted { json: file, .. } => file.path(),
             Self::File(path) => path,
-        };
+        }
+    }
+
+    /// Move the contents of a freshly read copy of this input into place.
+    ///
+    /// Regular files are read by jq directly, so there's nothing to replace for them.
+    fn replace_with(&self, fresh: InputFile) -> Result<(), Error> {
+        match (self, fresh) {
+            (Self::Stdin(old), InputFile::Stdin(new))
+            | (Self::Command(old), InputFile::Command(new)) => {
+                new.persist(old.path())?;
+            }
+            (
+                Self::Converted { original, json },
+                InputFile::Converted {
+                    original: fresh_original,
+                    json: fresh_json,
+                },
+            ) => {
+                fresh_json.persist(json.path())?;
+                original.replace_with(*fresh_original)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The regular file this input was read from, which can be read again when it changes.
+    fn source_file(&self) -> Option<&'a Path> {
+        match self {
+            Self::File(path) => Some(path),
+            Self::Converted { original, .. } => original.source_file(),
+            _ => None,
+        }
+    }
+
+    /// Where the raw output of `--from-cmd` is captured, before any conversion.
+    fn command_output(&self) -> Option<&Path> {
+        match self {
+            Self::Command(output) => Some(output.path()),
+            Self::Converted { original, .. } => original.command_output(),
+            _ => None,
+        }
+    }
+
+    /// The path of the input as it was given, before any conversion to JSON.
+    #[must_use]
+    pub fn original_path(&self) -> &Path {
+        match self {
+            Self::Converted { original, .. } => original.original_path(),
+            _ => self.path(),
+        }
+    }
+}
 
-        write!(f, "{}", bash_quote(path))
+impl std::fmt::Display for InputFile<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", bash_quote(self.path()))
     }
 }
 
+/// What this run of jq-repl set up for fzf, once the inputs and the runtime directory are ready.
+pub struct Runtime<'a> {
+    pub sample: Option<&'a Sample>,
+    /// The session being resumed
+    pub resumed: Option<&'a Session>,
+    /// Where the query and prompt are recorded when fzf quits, if anything reads them
+    pub state_file: Option<&'a Path>,
+    /// Where the pinned output is saved
+    pub pin_file: &'a Path,
+}
+
 pub fn build_fzf_cmd(
     opt: &Opt,
     config: &Config,
     history_file: Option<&Path>,
-    input_file_paths: &str,
+    files: &[InputFile],
+    runtime: &Runtime,
 ) -> Result<Command, Error> {
+    let Runtime {
+        sample,
+        resumed,
+        state_file,
+        pin_file,
+    } = *runtime;
     let jq_bin = &opt.jq_bin;
 
+    let input_files = match sample {
+        Some(sample) => (0..files.len())
+            .map(|i| bash_quote(sample.input_path(i)))
+            .collect(),
+        None => files
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect::<Vec<_>>(),
+    };
+    let original_file_paths = bash_quote_join(files.iter().map(InputFile::original_path));
+    let input_file_paths = input_file_paths(opt, &input_files);
+    let input_file_paths = input_file_paths.as_str();
+
     let jq_arg_prefix = get_jq_arg_prefix(opt);
 
     let mut fzf = Command::new(&opt.fzf_bin);
 
     // Add some jq-repl environment variables so they can be referenced from within
     fzf.env("JQ_REPL_VERSION", clap::crate_version!())
+        .env("JQ_REPL_BIN", std::env::current_exe()?)
         .env("JQ_REPL_JQ_BIN", &opt.jq_bin)
         .env("JQ_REPL_JQ_ARG_PREFIX", &jq_arg_prefix)
         .env("JQ_REPL_COLOR_FLAG", &opt.color_flag)
-        .env("JQ_REPL_NO_COLOR_FLAG", &opt.no_color_flag);
+        .env("JQ_REPL_NO_COLOR_FLAG", &opt.no_color_flag)
+        .env("JQ_REPL_DIFF_BIN", &opt.diff_bin)
+        .env("JQ_REPL_PIN_FILE", pin_file)
+        .env("JQ_REPL_FIXTURE_DIR", &opt.fixture_dir)
+        .env("JQ_REPL_INPUT_FILES", lasting_input_files(files).join(" "));
+
+    if let Some(command) = &opt.from_cmd {
+        fzf.env("JQ_REPL_FROM_CMD", command);
+    }
 
     // Pass lens commands as env vars so _jq-repl-transform can build the preview command.
     // Each lens is exposed as JQ_REPL_LENS_<NAME> (uppercased).
@@ -265,12 +860,10 @@ pub fn build_fzf_cmd(
         );
     }
 
-    let default_preview_window = "up,99%,border-bottom";
-
     // Setup layout and style
     fzf.args([
         "--disabled",
-        &format!("--preview-window={default_preview_window}"),
+        &format!("--preview-window={DEFAULT_PREVIEW_WINDOW}"),
         "--no-separator",
         "--info=hidden",
         "--query=.",
@@ -296,10 +889,57 @@ pub fn build_fzf_cmd(
         &opt.color_flag
     ));
 
+    // The label also shows which revisions are being read from git, whether sampling is active, and
+    // whether the output still matches the pinned output while it's compared against. Besides the
+    // bindings, `_jq-repl-transform` works it out again as the lens changes, with the query taken
+    // from the environment.
+    let diff_bin = bash_quote(&opt.diff_bin);
+    let mut preview_label = String::new();
+    let mut label_parts = vec![];
+
+    let revisions = opt
+        .files
+        .iter()
+        .filter_map(|file_name| RevisionSpec::parse(file_name))
+        .map(|spec| spec.to_string())
+        .collect::<Vec<_>>();
+    if !revisions.is_empty() {
+        let label = format!(" {} ", revisions.join(", "));
+        label_parts.push(format!("printf %s {}", bash_quote(&label)));
+        preview_label.push_str(&label);
+    }
+
+    if let Some(sample) = sample {
+        label_parts.push(sample.label_command());
+        preview_label.push_str(&sample.label());
+    }
+
+    let label_command = |query: &str| {
+        let mut command = format!(
+            "printf \"%s\" {query} | {} {}",
+            bash_quote(&opt.charcounter_bin),
+            &opt.charcounter_options.join(" "),
+        );
+        for part in &label_parts {
+            command.push_str("; ");
+            command.push_str(part);
+        }
+        // Only run the query a second time while the pinned lens is active
+        command.push_str(&format!(
+            "; case \"$FZF_PROMPT\" in \"{PINNED_PROGRAM}> \" | *\" {PINNED_PROGRAM}> \") \
+             {diff_bin} --label --pinned \"$JQ_REPL_PIN_FILE\" {query} {input_file_paths};; esac"
+        ));
+        command
+    };
+    let preview_label_command = label_command("{q}");
+    fzf.env("JQ_REPL_PREVIEW_LABEL", label_command("\"$FZF_QUERY\""));
+
+    if !preview_label.is_empty() {
+        fzf.arg(format!("--preview-label={preview_label}"));
+    }
+
     fzf.arg(format!(
-        "--bind=change:transform-preview-label:printf \"%s\" {{q}} | {} {}",
-        bash_quote(&opt.charcounter_bin),
-        &opt.charcounter_options.join(" "),
+        "--bind=change:transform-preview-label:{preview_label_command}"
     ))
     .arg(format!(
         "--bind=tab:transform-query:echo {{q}} | {}",
@@ -334,7 +974,7 @@ pub fn build_fzf_cmd(
         .join(","),
     ));
 
-    add_freeze_headers_binding(&mut fzf, default_preview_window, "alt-h", &[1, 2, 3, 0]);
+    add_freeze_headers_binding(&mut fzf, DEFAULT_PREVIEW_WINDOW, "alt-h", &[1, 2, 3, 0]);
 
     let transform_bin = &opt.transform_bin;
 
@@ -361,12 +1001,114 @@ pub fn build_fzf_cmd(
         ));
     }
 
+    // Compare the output between two inputs
+    if files.len() == 2 && !opt.pass_as_stdin {
+        fzf.arg(format!(
+            "--bind={}:bg-transform:{transform_bin} -p {DIFF_PROGRAM} -- {input_file_paths}",
+            config.keybinds.diff_inputs,
+        ));
+    }
+
+    // Pin the output, to compare later queries against it. The colon forms keep parentheses in
+    // file names from ending the actions early.
+    fzf.arg(format!(
+        "--bind={}:transform:{diff_bin} --save \"$JQ_REPL_PIN_FILE\" {{q}} {input_file_paths} && \
+         echo 'refresh-preview+transform-preview-label:bash -c \"$JQ_REPL_PREVIEW_LABEL\"'",
+        config.keybinds.pin_output,
+    ))
+    .arg(format!(
+        "--bind={}:transform:{transform_bin} -p {PINNED_PROGRAM} -- {input_file_paths}",
+        config.keybinds.diff_pinned,
+    ));
+
+    // Bring back the query, runtime flags and lens of a resumed session (later options win)
+    if let Some(session) = resumed {
+        fzf.arg(format!("--query={}", session.query))
+            .arg(format!("--prompt={}", session.prompt))
+            .arg(format!(
+                "--bind=start:bg-transform:{transform_bin} -- {input_file_paths}"
+            ));
+    }
+
     // Add a single binding to reset back to the default jq view
     fzf.arg(format!(
         "--bind={}:bg-transform:{transform_bin} -p -- {input_file_paths}",
         config.keybinds.reset_lens,
     ));
 
+    // Re-run the input command, only swapping in its output once it has finished successfully
+    if let Some(output) = files.iter().find_map(InputFile::command_output) {
+        let mut partial = output.as_os_str().to_owned();
+        partial.push(".partial");
+        let (output, partial) = (bash_quote(output), bash_quote(partial));
+
+        fzf.arg(format!(
+            "--bind={}:execute-silent(sh -c \"$JQ_REPL_FROM_CMD\" > {partial} && mv -f {partial} \
+             {output} || rm -f {partial})+refresh-preview",
+            config.keybinds.rerun_command,
+        ));
+    }
+
+    // Switch between the sample and the full input
+    if let Some(sample) = sample {
+        fzf.arg(format!(
+            "--bind={}:execute-silent({})+refresh-preview+transform-preview-label:\
+             {preview_label_command}",
+            config.keybinds.toggle_sample,
+            sample.toggle_command(),
+        ));
+    }
+
+    if let Some(state_file) = state_file {
+        fzf.args(state::exit_bindings(state_file));
+    }
+
+    // Accept the query, unless the snippet picker is open, where enter loads the snippet instead
+    let mut accept =
+        format!("--bind=enter:transform:{transform_bin} --accept --query {{q}} --selected {{}}");
+    if let Some(state_file) = state_file {
+        accept.push_str(&format!(" --state-file {}", bash_quote(state_file)));
+    }
+    fzf.arg(accept).arg(format!(
+        "--bind={}:transform:{transform_bin} --pick-snippet",
+        config.keybinds.pick_snippet,
+    ));
+
+    fzf.arg(format!(
+        "--bind={}:execute(\"$JQ_REPL_BIN\" snippets add -- {{q}})",
+        config.keybinds.save_snippet,
+    ))
+    .arg(format!(
+        "--bind={}:execute(\"$JQ_REPL_BIN\" export -- {{q}})",
+        config.keybinds.export_query,
+    ));
+
+    // Save the query, its input and output as a fixture for `jq-repl test`
+    fzf.arg(format!(
+        "--bind={}:execute(\"$JQ_REPL_BIN\" save-fixture -- {{q}} {})",
+        config.keybinds.save_fixture,
+        input_files.join(" "),
+    ));
+
+    // Copy the query, the output or a command line running it, which works over SSH by default
+    let clipboard_bin = bash_quote(&opt.clipboard_bin);
+    fzf.args([
+        format!(
+            "--bind={}:execute-silent:printf %s {{q}} | {clipboard_bin}",
+            config.keybinds.copy_query,
+        ),
+        format!(
+            "--bind={}:execute-silent:{jq_bin} {jq_arg_prefix} {} {{q}} {input_file_paths} | \
+             {clipboard_bin}",
+            config.keybinds.copy_output, opt.no_color_flag,
+        ),
+        format!(
+            "--bind={}:execute-silent:\"$JQ_REPL_BIN\" export --format command -- {{q}} | \
+             {clipboard_bin}",
+            config.keybinds.copy_command,
+        ),
+    ]);
+
     // Add bindings to open output in an external program
     add_external_bindings(
         &mut fzf,
@@ -375,6 +1117,7 @@ pub fn build_fzf_cmd(
         opt,
         config,
         input_file_paths,
+        &original_file_paths,
     );
 
     // Pass additional arguments given on the command line
@@ -385,6 +1128,36 @@ pub fn build_fzf_cmd(
     Ok(fzf)
 }
 
+/// Ask for a line of text on the terminal.
+pub(crate) fn ask(question: &str) -> std::io::Result<String> {
+    eprint!("{question}: ");
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// The (already quoted) input files as passed to jq.
+fn input_file_paths(opt: &Opt, input_files: &[String]) -> String {
+    if opt.pass_as_stdin {
+        format!("< {}", &input_files[0])
+    } else {
+        input_files.join(" ")
+    }
+}
+
+/// The (already quoted) inputs that are still there after jq-repl quits.
+///
+/// Temporary copies are gone by then, so those inputs are left for the command's standard input.
+fn lasting_input_files(files: &[InputFile]) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|file| match file {
+            InputFile::File(path) => Some(bash_quote(path)),
+            _ => None,
+        })
+        .collect()
+}
+
 fn add_freeze_headers_binding(
     fzf: &mut Command,
     default_preview_window: &str,
@@ -412,10 +1185,19 @@ fn add_external_bindings(
     opt: &Opt,
     config: &Config,
     input_file_paths: &str,
+    original_file_paths: &str,
 ) {
     let no_color_flag = &opt.no_color_flag;
 
     for external in config.external.values() {
+        if external.original_input {
+            fzf.arg(format!(
+                "--bind={}:execute:cat {original_file_paths} | {}",
+                external.key, external.command,
+            ));
+            continue;
+        }
+
         // Extra jq flags (e.g. "-c") are joined and inserted before the no-color flag
         let extra_flags = if external.jq_flags.is_empty() {
             String::new()
@@ -431,33 +1213,447 @@ fn add_external_bindings(
     }
 }
 
-fn get_files(positional_files: &[PathBuf]) -> Result<Vec<InputFile<'_>>, Error> {
+fn get_files(opt: &Opt) -> Result<Vec<InputFile<'_>>, Error> {
     let mut files: Vec<InputFile> = vec![];
 
     let has_piped_input = !std::io::stdin().is_terminal();
 
-    for file_name in positional_files {
-        if file_name == "-" {
-            let mut file = NamedTempFile::new()?;
-            std::io::copy(&mut std::io::stdin(), &mut file)?;
+    for file_name in &opt.files {
+        if file_name == "-" && opt.follow {
+            files.push(InputFile::Follow(NamedTempFile::new()?));
+        } else if file_name == "-" {
+            files.push(read_stdin(opt)?);
+        } else if let Some(spec) = RevisionSpec::parse(file_name) {
+            files.push(spec.show(|contents| read_input(opt, contents, file_name, None))?);
+        } else {
+            files.push(read_file(opt, file_name)?);
+        }
+    }
+
+    for text in &opt.json {
+        files.push(read_inline_json(opt, "--json", text)?);
+    }
+
+    for name in &opt.json_env {
+        let text = std::env::var(name).map_err(|source| Error::EnvVar {
+            name: name.clone(),
+            source,
+        })?;
+        files.push(read_inline_json(opt, &format!("${name}"), &text)?);
+    }
+
+    if let Some(db) = &opt.sqlite {
+        files.push(read_sqlite(opt, db)?);
+    }
+
+    if let Some(command) = &opt.from_cmd {
+        files.push(read_command(opt, run_input_command(command)?)?);
+    } else if has_piped_input && files.is_empty() && opt.follow {
+        files.push(InputFile::Follow(NamedTempFile::new()?));
+    } else if has_piped_input && files.is_empty() {
+        files.push(read_stdin(opt)?);
+    }
+
+    Ok(files)
+}
+
+/// The regular files the inputs were read from, paired with the index of the input.
+fn watched_files<'a>(files: &[InputFile<'a>]) -> Vec<(usize, &'a Path)> {
+    files
+        .iter()
+        .enumerate()
+        .filter_map(|(i, file)| file.source_file().map(|file_name| (i, file_name)))
+        .collect()
+}
+
+fn read_file<'a>(opt: &Opt, file_name: &'a Path) -> Result<InputFile<'a>, Error> {
+    let open_error = |source| Error::Open {
+        path: file_name.to_path_buf(),
+        source,
+    };
+
+    let kind = SourceKind::of(file_name).map_err(open_error)?;
+    match kind {
+        SourceKind::Missing => return Err(Error::MissingInput(file_name.to_path_buf())),
+        SourceKind::Other => return Err(Error::UnreadableInput(file_name.to_path_buf())),
+        SourceKind::Regular | SourceKind::Fifo | SourceKind::CharDevice => {}
+    }
+
+    let file = File::open(file_name).map_err(open_error)?;
+    if kind.is_stream() {
+        // Pipes can only be read once, so they're copied while showing how far along that is
+        let source = BufReader::new(Progress::new(file, file_name));
+        read_input(opt, source, file_name, None)
+    } else {
+        read_input(opt, BufReader::new(file), file_name, Some(file_name))
+    }
+}
+
+fn read_stdin<'a>(opt: &Opt) -> Result<InputFile<'a>, Error> {
+    let name = Path::new("-");
+    let source = BufReader::new(Progress::new(std::io::stdin().lock(), name));
+    read_input(opt, source, name, None)
+}
+
+/// Read JSON given on the command line, checking it up front since there's no file to fix.
+fn read_inline_json<'a>(opt: &Opt, name: &str, text: &str) -> Result<InputFile<'a>, Error> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(text.as_bytes())?;
+
+    // Unless it's checked while it's read anyway, as asked for or while it's converted
+    let checked_when_read = opt.lenient || (opt.validate.is_some() && !opt.raw_input);
+    if !checked_when_read {
+        validate::validate(validate::Validation::Strict, Path::new(name), file.path())?;
+    }
+
+    let source = BufReader::new(file.reopen()?);
+    read_input(opt, source, Path::new(name), None)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn read_sqlite<'a>(_opt: &Opt, _db: &Path) -> Result<InputFile<'a>, Error> {
+    Err(Error::NoSqlite)
+}
+
+/// Convert the rows of a `--sqlite` database to JSON.
+///
+/// The rows are the input, like the output of `--from-cmd`, rather than the database file itself.
+#[cfg(feature = "sqlite")]
+fn read_sqlite<'a>(opt: &Opt, db: &Path) -> Result<InputFile<'a>, Error> {
+    let query = match (&opt.table, &opt.sql) {
+        (Some(table), _) => sqlite::Query::Table(table),
+        (_, Some(sql)) => sqlite::Query::Sql(sql),
+        (None, None) => sqlite::Query::AllTables,
+    };
+
+    let mut json = NamedTempFile::new()?;
+    sqlite::to_json(db, query, opt.binary_format, opt.ndjson, &mut json).map_err(|source| {
+        Error::Format {
+            path: db.to_path_buf(),
+            source,
+        }
+    })?;
+
+    Ok(InputFile::Stdin(json))
+}
+
+/// Capture the output of a `--from-cmd` shell command.
+fn run_input_command(command: &str) -> Result<NamedTempFile, Error> {
+    let file = NamedTempFile::new()?;
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdout(file.reopen()?)
+        .status()?;
+
+    if status.success() {
+        Ok(file)
+    } else {
+        Err(Error::Command {
+            command: command.to_string(),
+            status,
+        })
+    }
+}
+
+/// Read the captured output of `--from-cmd` like standard input, keeping the capture itself to
+/// re-run the command into.
+fn read_command<'a>(opt: &Opt, output: NamedTempFile) -> Result<InputFile<'a>, Error> {
+    Ok(match read_command_output(opt, output.path())? {
+        InputFile::Converted { json, .. } => InputFile::Converted {
+            original: Box::new(InputFile::Command(output)),
+            json,
+        },
+        _ => InputFile::Command(output),
+    })
+}
+
+fn read_command_output<'a>(opt: &Opt, output: &Path) -> Result<InputFile<'a>, Error> {
+    let source = BufReader::new(File::open(output)?);
+    read_input(opt, source, Path::new("-"), None)
+}
+
+/// Check that a JSON input is well formed, replacing it with a repaired copy if allowed to.
+fn validate_input<'a>(
+    validation: validate::Validation,
+    original: InputFile<'a>,
+    name: &Path,
+) -> Result<InputFile<'a>, Error> {
+    match validate::validate(validation, name, original.path())? {
+        Some((json, invalid)) => {
+            let kept = match validation {
+                validate::Validation::Lines => "only the valid lines",
+                _ => "the values before it",
+            };
+            eprintln!("warning: {invalid}\ncontinuing with {kept}");
+
+            Ok(InputFile::Converted {
+                original: Box::new(original),
+                json,
+            })
+        }
+        None => Ok(original),
+    }
+}
 
-            files.push(InputFile::Stdin(file));
-        } else if !file_name.is_file() {
+/// Read an input, decompressing it and converting it to JSON if needed.
+///
+/// `path` is set when the input is a regular file that can be read again later. Otherwise, the
+/// input is copied into a temporary file first, so the original is still available to externals.
+fn read_input<'a, 's>(
+    opt: &Opt,
+    mut source: impl BufRead + 's,
+    name: &Path,
+    path: Option<&'a Path>,
+) -> Result<InputFile<'a>, Error> {
+    let read_error = |source| Error::Format {
+        path: name.to_path_buf(),
+        source,
+    };
+
+    // Raw text could start with anything, so only the extension says whether it's compressed
+    let header = if opt.raw_input {
+        &[][..]
+    } else {
+        source.fill_buf()?
+    };
+
+    // Compressed files can't be handed to jq directly, so they're always decompressed into a copy
+    let (mut source, path, format_name): (Box<dyn BufRead + 's>, _, _) =
+        match Compression::detect(name, header) {
+            Some(compression) => (
+                Box::new(BufReader::new(compression.decoder(source)?)),
+                None,
+                Compression::strip_extension(name),
+            ),
+            None => (Box::new(source), path, name.to_path_buf()),
+        };
+
+    let format = match opt.input_format {
+        // Raw input is meant to be read line by line, so only convert it when explicitly asked to
+        InputFormat::Auto if opt.raw_input => InputFormat::Json,
+        format => format.resolve((name != "-").then_some(&format_name), &mut source)?,
+    };
+
+    let format = if opt.lenient && format == InputFormat::Json {
+        InputFormat::Json5
+    } else {
+        format
+    };
+
+    let original = match path {
+        Some(path) => InputFile::File(path),
+        None => {
             let mut file = NamedTempFile::new()?;
-            let mut source = File::open(file_name)?;
-            std::io::copy(&mut source, &mut file)?;
+            std::io::copy(&mut source, &mut file).map_err(|err| read_error(err.into()))?;
+            InputFile::Stdin(file)
+        }
+    };
 
-            files.push(InputFile::Stdin(file));
-        } else {
-            files.push(InputFile::File(file_name));
+    let input = if format.is_json() {
+        match opt.validate.filter(|_| !opt.raw_input) {
+            Some(validation) => validate_input(validation, original, name)?,
+            None => original,
         }
+    } else {
+        let source = BufReader::new(File::open(original.path())?);
+        let mut json = NamedTempFile::new()?;
+        format::convert(format, opt.binary_format, source, &mut json).map_err(read_error)?;
+
+        InputFile::Converted {
+            original: Box::new(original),
+            json,
+        }
+    };
+
+    if !opt.with_filename {
+        return Ok(input);
     }
 
-    if has_piped_input && files.is_empty() {
-        let mut file = NamedTempFile::new()?;
-        std::io::copy(&mut std::io::stdin(), &mut file)?;
-        files.push(InputFile::Stdin(file));
+    let source = BufReader::new(File::open(input.path())?);
+    let mut json = NamedTempFile::new()?;
+    format::wrap_with_filename(&name.to_string_lossy(), source, &mut json).map_err(read_error)?;
+
+    Ok(InputFile::Converted {
+        original: Box::new(input),
+        json,
+    })
+}
+
+/// Concatenate inputs into one, so they can all be passed to jq as standard input.
+fn combine_inputs<'a>(files: &[InputFile]) -> Result<InputFile<'a>, Error> {
+    let mut combined = NamedTempFile::new()?;
+    for file in files {
+        std::io::copy(&mut File::open(file.path())?, &mut combined)?;
     }
 
-    Ok(files)
+    Ok(InputFile::Stdin(combined))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Opt {
+        Opt::try_parse_from(std::iter::once("jq-repl").chain(args.iter().copied())).unwrap()
+    }
+
+    const QUERY: &str = ".[] | select(.name == $name)";
+
+    #[test]
+    fn command_line_keeps_jq_args_and_flags() {
+        // --clean leaves out the default flags, including the one for raw input
+        let mut opt = parse(&[
+            "--jq-bin=jq",
+            "--clean",
+            "-R",
+            "--raw-input-flag=--raw-input",
+            "--",
+            "--arg",
+            "name",
+            "ada",
+        ]);
+        push_input_flags(&mut opt);
+        let files = [InputFile::File(Path::new("users list.txt"))];
+        let prompt = "-c> ".parse().unwrap();
+
+        assert_eq!(
+            command_line(&opt, &Config::default(), &files, &prompt, QUERY),
+            "jq --arg name ada --raw-input -c $'.[] | select(.name == $name)' $'users list.txt'"
+        );
+    }
+
+    #[test]
+    fn command_line_pipes_into_lens() {
+        let opt = parse(&["--jq-bin=jq", "--clean"]);
+        let config: Config =
+            toml::from_str("[lens.gron]\ncommand = 'gron'\nkey = 'ctrl-space'").unwrap();
+        let files = [InputFile::File(Path::new("users.json"))];
+        let prompt = "gron> ".parse().unwrap();
+
+        assert_eq!(
+            command_line(&opt, &config, &files, &prompt, "."),
+            "jq . users.json | gron"
+        );
+    }
+
+    #[test]
+    fn command_line_leaves_stdin_out() {
+        let opt = parse(&["--jq-bin=jq", "--clean"]);
+        let files = [
+            InputFile::Stdin(NamedTempFile::new().unwrap()),
+            InputFile::File(Path::new("users.json")),
+        ];
+        let prompt = "> ".parse().unwrap();
+        assert_eq!(
+            command_line(&opt, &Config::default(), &files[..1], &prompt, "."),
+            "jq ."
+        );
+        assert_eq!(
+            command_line(&opt, &Config::default(), &files, &prompt, "."),
+            "jq . users.json"
+        );
+    }
+
+    #[test]
+    fn apply_reads_input_like_the_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let (file, out) = (dir.path().join("a.txt"), dir.path().join("out"));
+        std::fs::write(&file, "text").unwrap();
+
+        let mut opt = Opt::try_parse_from([
+            "jq-repl".as_ref(),
+            "--jq-bin=echo".as_ref(),
+            "-R".as_ref(),
+            "apply".as_ref(),
+            "--output-dir".as_ref(),
+            out.as_os_str(),
+            ".".as_ref(),
+            file.as_os_str(),
+        ])
+        .unwrap();
+        let Some(SubCommand::Apply(args)) = opt.command.take() else {
+            panic!("expected the apply subcommand");
+        };
+        run_apply(opt, &args).unwrap();
+
+        let command = std::fs::read_to_string(out.join("a.txt")).unwrap();
+        assert!(command.contains(" -R "), "{command}");
+    }
+
+    #[test]
+    fn watches_the_files_inputs_came_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, lenient) = (dir.path().join("a.json"), dir.path().join("b.json5"));
+        std::fs::write(&plain, "{}").unwrap();
+        std::fs::write(&lenient, "{a: 1,}").unwrap();
+
+        let opt = Opt::try_parse_from([
+            "jq-repl".as_ref(),
+            "--json=[1]".as_ref(),
+            plain.as_os_str(),
+            lenient.as_os_str(),
+        ])
+        .unwrap();
+        let files = get_files(&opt).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            watched_files(&files),
+            [(0, plain.as_path()), (1, lenient.as_path())]
+        );
+    }
+
+    #[test]
+    fn inline_json_comes_after_files() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "{}").unwrap();
+
+        // Cargo sets this while testing, and a version number happens to be JSON
+        let opt = Opt::try_parse_from([
+            "jq-repl".as_ref(),
+            "--json-env=CARGO_PKG_VERSION_MAJOR".as_ref(),
+            "--json=[1]".as_ref(),
+            file.path().as_os_str(),
+        ])
+        .unwrap();
+        let files = get_files(&opt).unwrap();
+
+        let contents = files
+            .iter()
+            .map(|file| std::fs::read_to_string(file.path()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, ["{}", "[1]", env!("CARGO_PKG_VERSION_MAJOR")]);
+    }
+
+    #[test]
+    fn invalid_inline_json_is_an_error() {
+        let opt = parse(&["--json-env=JQ_REPL_TEST_UNSET"]);
+        assert!(matches!(get_files(&opt), Err(Error::EnvVar { .. })));
+
+        let opt = parse(&["--json={\"a\":"]);
+        assert!(matches!(get_files(&opt), Err(Error::InvalidJson(_))));
+
+        // Repaired like any other input when asked to
+        let opt = parse(&["--validate=prefix", "--json=1 2 {"]);
+        let files = get_files(&opt).unwrap();
+        assert_eq!(std::fs::read_to_string(files[0].path()).unwrap(), "1 2 ");
+    }
+
+    #[test]
+    fn command_output_is_converted() {
+        let opt = parse(&["--lenient", "--from-cmd", "true"]);
+        let input = read_command(&opt, run_input_command("printf '{a: 1,}'").unwrap()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(input.path()).unwrap(),
+            "{\"a\":1}\n"
+        );
+
+        // Re-running the command only replaces the raw output, which is then read again
+        let output = input.command_output().unwrap();
+        std::fs::write(output, "[1,]").unwrap();
+        input
+            .replace_with(read_command_output(&opt, output).unwrap())
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(input.path()).unwrap(), "[1]\n");
+    }
 }
@@ -0,0 +1,73 @@
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A connection to a running fzf through its `--listen` socket.
+///
+/// This lets jq-repl trigger fzf actions (like refreshing the preview) from outside of a key
+/// binding, e.g. when an input file changes.
+#[derive(Debug, Clone)]
+pub struct FzfRemote {
+    socket: PathBuf,
+}
+
+impl FzfRemote {
+    /// Use a socket inside `dir`, which should only be accessible to the current user.
+    #[must_use]
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            socket: dir.join("fzf.sock"),
+        }
+    }
+
+    /// The argument telling fzf to listen on the socket.
+    #[must_use]
+    pub fn listen_arg(&self) -> String {
+        format!("--listen={}", self.socket.display())
+    }
+
+    /// Ask fzf to run the given action(s), e.g. `refresh-preview`.
+    pub fn send(&self, action: &str) -> std::io::Result<()> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+        stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{action}",
+            action.len()
+        )?;
+
+        // fzf closes the connection once the action has been queued
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn sends_action_as_http_post() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = FzfRemote::new(dir.path());
+        let listener = UnixListener::bind(dir.path().join("fzf.sock")).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 1024];
+            let len = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+            String::from_utf8(request[..len].to_vec()).unwrap()
+        });
+
+        remote.send("refresh-preview").unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Length: 15\r\n"));
+        assert!(request.ends_with("\r\n\r\nrefresh-preview"));
+    }
+}
@@ -8,6 +8,16 @@ fn main() {
         {
             std::process::exit(code);
         }
+        // Shown as written: the snippet pointing at invalid JSON spans several lines, and the path
+        // an input couldn't be read from is easier to spot without the debug formatting
+        if let Error::InvalidJson(_)
+        | Error::MissingInput(_)
+        | Error::UnreadableInput(_)
+        | Error::Open { .. } = err
+        {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
         eprintln!("{err:#?}");
         std::process::exit(1);
     }
@@ -1,3 +1,8 @@
+use crate::apply::Destination;
+use crate::export::ExportFormat;
+use crate::format::{BinaryFormat, InputFormat};
+use crate::session;
+use crate::validate::Validation;
 use clap::{ValueHint, builder::ArgPredicate};
 use clap_complete::Shell;
 use std::path::PathBuf;
@@ -53,6 +58,28 @@ pub struct Opt {
     )]
     pub completion_bin: String,
 
+    /// Path to a program that shows how a query's output differs between two inputs
+    ///
+    /// It's given the query followed by both inputs, and prints the differences by JSON path.
+    #[arg(
+        long,
+        env = "JQ_REPL_DIFF_BIN",
+        default_value = "_jq-repl-diff",
+        value_hint = ValueHint::CommandName,
+    )]
+    pub diff_bin: String,
+
+    /// Path to a program that copies standard input to the clipboard
+    ///
+    /// The default uses the OSC 52 escape sequence, which works over SSH and inside tmux.
+    #[arg(
+        long,
+        env = "JQ_REPL_CLIPBOARD_BIN",
+        default_value = "_jq-repl-clipboard",
+        value_hint = ValueHint::CommandName,
+    )]
+    pub clipboard_bin: String,
+
     /// Path to a program that atomically updates the fzf prompt and preview command
     ///
     /// Reads `FZF_PROMPT`, applies any flag/program changes, and prints fzf actions for
@@ -112,6 +139,59 @@ pub struct Opt {
     #[arg(short = 'R', long)]
     pub raw_input: bool,
 
+    /// Print the output of the query to stdout when it's accepted with enter
+    ///
+    /// The query is run once more over the full input, without color but with the runtime flags
+    /// (like compact output) that were active. fzf draws on the terminal directly, so jq-repl can
+    /// sit in the middle of a pipeline: `curl ... | jq-repl --print-result | next-tool`.
+    #[arg(long, group = "print_accepted")]
+    pub print_result: bool,
+
+    /// Print the query to stdout when it's accepted with enter
+    #[arg(long, group = "print_accepted")]
+    pub print_query: bool,
+
+    /// Print a shell command running the query to stdout when it's accepted with enter
+    ///
+    /// The command includes the jq arguments, the runtime flags that were active, and the active
+    /// lens as a trailing pipe. Inputs that aren't regular files (like standard input) are left out,
+    /// so the command reads them from its standard input instead.
+    #[arg(long, group = "print_accepted")]
+    pub print_command: bool,
+
+    /// Replace the input file with the query's output when it's accepted with enter
+    ///
+    /// The file is replaced all at once, and the original is kept next to it with a `.bak`
+    /// extension. The input has to be a single JSON file, rather than standard input or a file
+    /// that was converted to JSON. Nothing is written if jq fails or gives no output.
+    #[arg(long, conflicts_with = "print_accepted")]
+    pub write_back: bool,
+
+    /// Once the query is accepted with enter, also run it on these files
+    ///
+    /// Directories and glob patterns are expanded the same way as the inputs. The outputs are
+    /// written to `--output-dir`, or replace the files with `--in-place`.
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::AnyPath,
+        conflicts_with_all = ["print_accepted", "write_back"],
+        requires = "destination",
+    )]
+    pub apply_to: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub destination: DestinationArgs,
+
+    /// Directory where fixtures are saved, and read from by `jq-repl test`
+    #[arg(
+        long,
+        env = "JQ_REPL_FIXTURE_DIR",
+        default_value = "jq-repl-fixtures",
+        value_hint = ValueHint::DirPath,
+    )]
+    pub fixture_dir: PathBuf,
+
     /// Print the fzf command that would be run to stdout and exit.
     #[arg(long, visible_alias = "print-fzf-command")]
     pub show_fzf_command: bool,
@@ -166,9 +246,159 @@ pub struct Opt {
     )]
     pub raw_input_flag: String,
 
+    /// Format of the input files
+    ///
+    /// Anything other than JSON is converted to JSON before being passed to jq. With `auto`, the
+    /// format is guessed from each file's extension or its first few bytes, falling back to JSON.
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto)]
+    pub input_format: InputFormat,
+
+    /// Accept comments, trailing commas and the rest of JSON5 in JSON input
+    ///
+    /// Lenient input is normalized into strict JSON before being passed to jq. Externals with
+    /// `original_input` set still receive the file as it was written.
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Check that JSON input is well formed before starting
+    ///
+    /// By default, the first error is reported with its line, column and a snippet. With `prefix`
+    /// or `lines`, jq-repl starts anyway with the values before the error, or with only the valid
+    /// lines of NDJSON input, and warns about what was left out.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "strict"
+    )]
+    pub validate: Option<Validation>,
+
+    /// Wrap each input value as `{"file": ..., "value": ...}`
+    ///
+    /// This tells values apart by the file they came from, even with `--pass-as-stdin` (where the
+    /// wrapped inputs are combined into one) or with interpreters lacking `input_filename`.
+    #[arg(long, conflicts_with_all = ["raw_input", "follow"])]
+    pub with_filename: bool,
+
+    /// How binary blobs in MessagePack, CBOR and BSON input are shown
+    #[arg(long, value_enum, default_value_t = BinaryFormat::Array)]
+    pub binary_format: BinaryFormat,
+
+    /// Read input from the output of a shell command
+    ///
+    /// The command is run with `sh -c` at startup, and again whenever the `rerun_command` key
+    /// binding (alt-r by default) is pressed, refreshing the preview without restarting. Its output
+    /// is read like standard input, so it's converted and checked the same way each time.
+    #[arg(long, value_name = "COMMAND", value_hint = ValueHint::CommandString)]
+    pub from_cmd: Option<String>,
+
+    /// Use the given JSON text as an input, after any files
+    ///
+    /// Unlike piping it in with `echo`, this keeps standard input free. Can be repeated.
+    #[arg(long, value_name = "TEXT")]
+    pub json: Vec<String>,
+
+    /// Use the JSON in an environment variable as an input, after any `--json` text
+    ///
+    /// Can be repeated.
+    #[arg(long, value_name = "VAR")]
+    pub json_env: Vec<String>,
+
+    /// Use the rows of a SQLite database as an input, after any inline JSON
+    ///
+    /// Without `--table` or `--sql`, every table is read into an object of arrays keyed by table
+    /// name. The database is opened read-only. Needs jq-repl to be built with the `sqlite` feature.
+    #[arg(long, value_name = "DB", value_hint = ValueHint::FilePath)]
+    pub sqlite: Option<PathBuf>,
+
+    /// Read the rows of this table from the `--sqlite` database
+    #[arg(long, requires = "sqlite", group = "sqlite_query")]
+    pub table: Option<String>,
+
+    /// Read the rows returned by this SQL query from the `--sqlite` database
+    #[arg(
+        long,
+        value_name = "QUERY",
+        requires = "sqlite",
+        group = "sqlite_query"
+    )]
+    pub sql: Option<String>,
+
+    /// Pass SQLite rows as a stream of objects instead of one array
+    #[arg(long, requires = "sqlite_query")]
+    pub ndjson: bool,
+
+    /// Refresh the preview whenever one of the input files changes
+    ///
+    /// Files that were converted to JSON are converted again first.
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Start right away and keep reading standard input in the background
+    ///
+    /// New records (lines) are added to the input as they arrive, and the preview is refreshed
+    /// periodically, e.g. for `tail -f app.log | jq-repl --follow`. Standard input is passed through
+    /// as-is, without any format conversion.
+    #[arg(long)]
+    pub follow: bool,
+
+    /// Only keep the last N records of standard input in `--follow` mode
+    #[arg(long, value_name = "N", requires = "follow")]
+    pub follow_window: Option<usize>,
+
+    /// Preview a random sample of N records from each input, instead of all of them
+    ///
+    /// Records are top-level JSON values, or lines with `--raw-input`. The `toggle_sample` key
+    /// binding (alt-s by default) switches between the sample and the full input.
+    #[arg(long, value_name = "N", conflicts_with_all = ["head", "follow"])]
+    pub sample: Option<usize>,
+
+    /// Preview only the first N records from each input, like `--sample`
+    #[arg(long, value_name = "N", conflicts_with = "follow")]
+    pub head: Option<usize>,
+
+    /// Save the query, flags, lens and inputs when quitting, to pick up later with `--resume`
+    ///
+    /// Inputs that only exist while jq-repl runs, like standard input, are copied into the
+    /// session. Sessions are stored in `$XDG_DATA_HOME/jq-repl/sessions`.
+    #[arg(
+        long,
+        value_name = "NAME",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = session::DEFAULT_NAME,
+        value_parser = session::parse_name,
+    )]
+    pub save_session: Option<String>,
+
+    /// Reopen a saved session where it was left off, and keep saving to it
+    #[arg(
+        long,
+        value_name = "NAME",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = session::DEFAULT_NAME,
+        value_parser = session::parse_name,
+        conflicts_with_all = ["files", "from_cmd", "json", "json_env", "sqlite", "follow"],
+    )]
+    pub resume: Option<String>,
+
+    /// List saved sessions with their last query, most recent first
+    #[arg(long)]
+    pub list_sessions: bool,
+
     /// JSON files to read from (defaults to standard input)
     ///
-    /// If one of the files is "-", insert stdin at that point.
+    /// If one of the files is "-", insert stdin at that point. Directories are replaced by the files
+    /// inside them, recursively. Quoted glob patterns like 'fixtures/**/*.json' are expanded too.
+    /// Either way, the files are sorted by path.
+    ///
+    /// A file can also be read from a git revision with `REV:path` (e.g. `HEAD~3:fixtures/a.json`),
+    /// where the path is resolved like `git show` does.
+    ///
+    /// A file named like a subcommand (e.g. `test`) has to be written as `./test`.
     pub files: Vec<PathBuf>,
 
     /// Print tab-completion for the given shell to stdout
@@ -194,6 +424,154 @@ pub struct Opt {
     /// Additional args passed to `jq`
     #[arg(last = true)]
     pub jq_args: Vec<String>,
+
+    #[command(subcommand)]
+    pub command: Option<SubCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SubCommand {
+    /// Manage saved query snippets
+    ///
+    /// Snippets are stored in `$XDG_DATA_HOME/jq-repl/snippets.json`. Inside jq-repl, save the
+    /// current query with ctrl-s, and pick a snippet to load with ctrl-o.
+    #[command(subcommand)]
+    Snippets(SnippetsCommand),
+
+    /// Export a query as a bash script, a file for `jq -f`, a `def` in a module, or a command
+    ///
+    /// The runtime flags, jq arguments and inputs are exported along with it. Inside jq-repl, alt-x
+    /// exports the current query, asking for the format and where to write it.
+    Export(ExportArgs),
+
+    /// Run a query on many files, writing the outputs to a directory or back over the files
+    ///
+    /// Files that fail are reported as they happen, and the rest are still run, followed by a
+    /// summary.
+    Apply(ApplyArgs),
+
+    /// Save a query, its input and its current output as a fixture in `--fixture-dir`
+    ///
+    /// Inside jq-repl, alt-F saves the current query as a fixture, asking for its name.
+    SaveFixture {
+        /// Name of the fixture, replacing any with the same name
+        #[arg(long, value_parser = session::parse_name)]
+        name: Option<String>,
+
+        /// The prompt, which holds the runtime flags
+        #[arg(long, env = "FZF_PROMPT", allow_hyphen_values = true)]
+        prompt: Option<String>,
+
+        query: String,
+
+        /// Inputs to combine into the fixture's input
+        inputs: Vec<PathBuf>,
+    },
+
+    /// Run the fixtures in `--fixture-dir` again, failing if any output changed
+    Test {
+        /// Names of the fixtures to run (defaults to all of them)
+        #[arg(value_parser = session::parse_name)]
+        names: Vec<String>,
+    },
+}
+
+/// Where the outputs go when a query is run on many files.
+#[derive(Debug, clap::Args)]
+#[group(id = "destination", multiple = false)]
+pub struct DestinationArgs {
+    /// Write each output to this directory, under the same relative path as its input
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Replace each file with its output, keeping the original with a `.bak` extension
+    #[arg(long)]
+    pub in_place: bool,
+}
+
+impl DestinationArgs {
+    #[must_use]
+    pub fn get(&self) -> Option<Destination<'_>> {
+        match &self.output_dir {
+            Some(dir) => Some(Destination::Dir(dir)),
+            None => self.in_place.then_some(Destination::InPlace),
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ApplyArgs {
+    #[command(flatten)]
+    pub destination: DestinationArgs,
+
+    pub query: String,
+
+    /// Files to run the query on, where directories and glob patterns are expanded
+    #[arg(required = true, value_hint = ValueHint::AnyPath)]
+    pub files: Vec<PathBuf>,
+
+    /// Additional args passed to `jq`
+    #[arg(last = true)]
+    pub jq_args: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+    #[arg(long, short, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// Where to write the script or `.jq` file (defaults to standard output)
+    #[arg(long, short, value_hint = ValueHint::FilePath, conflicts_with = "name")]
+    pub output: Option<PathBuf>,
+
+    /// Name of the function to define, for `--format def`
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Module in `--jq-repl-lib` to append the function to (defaults to its `.jq` file)
+    #[arg(long, requires = "name")]
+    pub module: Option<String>,
+
+    /// The prompt, which holds the runtime flags
+    #[arg(long, env = "FZF_PROMPT", allow_hyphen_values = true)]
+    pub prompt: Option<String>,
+
+    pub query: String,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SnippetsCommand {
+    /// List snippets as tab-separated name, description, tags and query
+    List {
+        /// Only list snippets with this tag
+        #[arg(long, short)]
+        tag: Option<String>,
+    },
+
+    /// Save a query as a snippet, asking for anything not given when run in a terminal
+    Add {
+        /// Name of the snippet, replacing any with the same name
+        #[arg(long, short)]
+        name: Option<String>,
+
+        #[arg(long, short)]
+        description: Option<String>,
+
+        /// Tag the snippet, to filter it with `snippets list --tag`
+        #[arg(long = "tag", short, value_name = "TAG")]
+        tags: Vec<String>,
+
+        query: String,
+    },
+
+    /// Remove snippets by name
+    Rm {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Print all snippets as a JSON object keyed by name
+    Export,
 }
 
 #[cfg(test)]
@@ -204,4 +582,45 @@ mod tests {
     fn app_sanity_check() {
         <Opt as clap::CommandFactory>::command().debug_assert();
     }
+
+    #[test]
+    fn resumed_sessions_keep_their_inputs() {
+        use clap::Parser;
+
+        for input in ["--json={}", "--json-env=HOME", "--from-cmd=true", "a.json"] {
+            let args = ["jq-repl", "--resume=work", input];
+            assert!(Opt::try_parse_from(args).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn fixture_names_stay_in_fixture_dir() {
+        use clap::Parser;
+
+        for name in ["../src", "/home/me"] {
+            let args = ["jq-repl", "save-fixture", "--name", name, "."];
+            assert!(Opt::try_parse_from(args).is_err(), "{name}");
+        }
+        assert!(Opt::try_parse_from(["jq-repl", "save-fixture", "--name", "keys", "."]).is_ok());
+
+        for name in ["../x", "/abs"] {
+            assert!(
+                Opt::try_parse_from(["jq-repl", "test", name]).is_err(),
+                "{name}"
+            );
+        }
+        assert!(Opt::try_parse_from(["jq-repl", "test", "keys"]).is_ok());
+    }
+
+    #[test]
+    fn files_named_like_subcommands() {
+        use clap::Parser;
+
+        let opt = Opt::try_parse_from(["jq-repl", "test"]).unwrap();
+        assert!(matches!(opt.command, Some(SubCommand::Test { .. })));
+
+        let opt = Opt::try_parse_from(["jq-repl", "./test"]).unwrap();
+        assert!(opt.command.is_none());
+        assert_eq!(opt.files, [PathBuf::from("./test")]);
+    }
 }
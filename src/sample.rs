@@ -0,0 +1,262 @@
+use crate::bash_quote;
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// How records are picked for a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMode {
+    /// The first N records
+    Head(usize),
+    /// N records picked at random, kept in their original order
+    Random(usize),
+}
+
+impl std::fmt::Display for SampleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Head(size) => write!(f, "first {size}"),
+            Self::Random(size) => write!(f, "sample of {size}"),
+        }
+    }
+}
+
+/// Smaller copies of the inputs, used for previews until toggled off.
+///
+/// Inside `dir`, the `sample` and `full` directories each hold one entry per input, and `current`
+/// is a symlink to one of them. jq is pointed at `current/<n>`, so switching between the sample
+/// and the full input only needs the symlink to change.
+#[derive(Debug)]
+pub struct Sample {
+    dir: PathBuf,
+    mode: SampleMode,
+}
+
+impl Sample {
+    /// Write a sample of each input file into `dir`.
+    ///
+    /// Records are lines with `raw` input, and top-level JSON values otherwise.
+    pub fn create(
+        dir: &Path,
+        inputs: &[&Path],
+        mode: SampleMode,
+        raw: bool,
+    ) -> Result<Self, crate::Error> {
+        let sample_dir = dir.join("sample");
+        let full_dir = dir.join("full");
+        std::fs::create_dir_all(&sample_dir)?;
+        std::fs::create_dir_all(&full_dir)?;
+
+        for (i, input) in inputs.iter().enumerate() {
+            let records = read_records(BufReader::new(File::open(input)?), raw);
+
+            let mut sample = BufWriter::new(File::create(sample_dir.join(i.to_string()))?);
+            for record in pick(records, mode) {
+                let record = record.map_err(|source| crate::Error::Format {
+                    path: input.to_path_buf(),
+                    source,
+                })?;
+                sample.write_all(&record)?;
+                sample.write_all(b"\n")?;
+            }
+            sample.flush()?;
+
+            std::os::unix::fs::symlink(
+                std::fs::canonicalize(input)?,
+                full_dir.join(i.to_string()),
+            )?;
+        }
+
+        std::os::unix::fs::symlink("sample", dir.join("current"))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            mode,
+        })
+    }
+
+    /// The path jq should read the input at `index` from.
+    #[must_use]
+    pub fn input_path(&self, index: usize) -> PathBuf {
+        self.dir.join("current").join(index.to_string())
+    }
+
+    /// A shell command switching between the sample and the full input.
+    ///
+    /// This avoids parentheses, so it can be used inside fzf actions like `execute-silent(...)`.
+    #[must_use]
+    pub fn toggle_command(&self) -> String {
+        format!(
+            "{} && ln -sfn full {current} || ln -sfn sample {current}",
+            self.is_active_command(),
+            current = bash_quote(self.dir.join("current")),
+        )
+    }
+
+    /// A shell command printing a label when the sample is active.
+    #[must_use]
+    pub fn label_command(&self) -> String {
+        format!(
+            "{} && printf %s {}",
+            self.is_active_command(),
+            bash_quote(self.label())
+        )
+    }
+
+    fn is_active_command(&self) -> String {
+        format!(
+            "test {} -ef {}",
+            bash_quote(self.dir.join("current")),
+            bash_quote(self.dir.join("sample"))
+        )
+    }
+
+    /// The label shown while the sample is active.
+    #[must_use]
+    pub fn label(&self) -> String {
+        format!(" [{}] ", self.mode)
+    }
+}
+
+type Record = Result<Vec<u8>, crate::format::FormatError>;
+
+fn read_records<'a>(reader: impl BufRead + 'a, raw: bool) -> Box<dyn Iterator<Item = Record> + 'a> {
+    if raw {
+        return Box::new(reader.split(b'\n').map(|line| line.map_err(Into::into)));
+    }
+
+    // Keep each value as it was written rather than parsing it completely, since only some of them
+    // will be kept
+    Box::new(
+        serde_json::Deserializer::from_reader(reader)
+            .into_iter::<Box<serde_json::value::RawValue>>()
+            .map(|value| Ok(value?.get().as_bytes().to_vec())),
+    )
+}
+
+fn pick(records: impl Iterator<Item = Record>, mode: SampleMode) -> Vec<Record> {
+    match mode {
+        SampleMode::Head(size) => records.take(size).collect(),
+        SampleMode::Random(size) => reservoir(records, size, &mut Rng::new()),
+    }
+}
+
+/// Pick `size` records at random in a single pass, keeping them in their original order.
+fn reservoir(records: impl Iterator<Item = Record>, size: usize, rng: &mut Rng) -> Vec<Record> {
+    let mut reservoir: Vec<(usize, Record)> = Vec::with_capacity(size);
+
+    for (i, record) in records.enumerate() {
+        // Errors are always kept, so a malformed input isn't silently ignored
+        if record.is_err() {
+            return vec![record];
+        }
+
+        if reservoir.len() < size {
+            reservoir.push((i, record));
+        } else {
+            let j = rng.below(i + 1);
+            if j < size {
+                reservoir[j] = (i, record);
+            }
+        }
+    }
+
+    reservoir.sort_by_key(|(i, _)| *i);
+    reservoir.into_iter().map(|(_, record)| record).collect()
+}
+
+/// A small xorshift generator; sampling doesn't need anything stronger.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        // Randomly seeded by the standard library, for lack of a dedicated source
+        Self(RandomState::new().build_hasher().finish() | 1)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        usize::try_from(self.0 % bound as u64).expect("value is below a usize bound")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn records(input: &str, raw: bool) -> Vec<String> {
+        read_records(input.as_bytes(), raw)
+            .map(|record| String::from_utf8(record.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn json_records_span_lines() {
+        let records = records("{\n  \"a\": 1\n}\n[2]\n3", false);
+        assert_eq!(records, ["{\n  \"a\": 1\n}", "[2]", "3"]);
+    }
+
+    #[test]
+    fn raw_records_are_lines() {
+        assert_eq!(records("a\nb\n", true), ["a", "b"]);
+    }
+
+    #[test]
+    fn head_takes_first_records() {
+        let picked = pick(read_records(&b"1 2 3 4"[..], false), SampleMode::Head(2));
+        let picked: Vec<_> = picked.into_iter().map(Result::unwrap).collect();
+        assert_eq!(picked, [b"1", b"2"]);
+    }
+
+    #[test]
+    fn reservoir_keeps_order_and_size() {
+        let input = (0..100)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let records = read_records(input.as_bytes(), false);
+        let picked: Vec<usize> = reservoir(records, 10, &mut Rng(42))
+            .into_iter()
+            .map(|record| String::from_utf8(record.unwrap()).unwrap().parse().unwrap())
+            .collect();
+
+        assert_eq!(picked.len(), 10);
+        assert!(picked.is_sorted());
+    }
+
+    #[test]
+    fn creates_toggleable_sample() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.json");
+        std::fs::write(&input, "1\n2\n3\n").unwrap();
+
+        let sample_dir = dir.path().join("runtime");
+        let sample = Sample::create(&sample_dir, &[&input], SampleMode::Head(2), false).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(sample.input_path(0)).unwrap(),
+            "1\n2\n"
+        );
+
+        let toggle = Command::new("sh")
+            .arg("-c")
+            .arg(sample.toggle_command())
+            .status();
+        assert!(toggle.unwrap().success());
+        assert_eq!(
+            std::fs::read_to_string(sample.input_path(0)).unwrap(),
+            "1\n2\n3\n"
+        );
+
+        let label = Command::new("sh")
+            .arg("-c")
+            .arg(sample.label_command())
+            .output();
+        assert!(label.unwrap().stdout.is_empty());
+    }
+}
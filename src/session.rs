@@ -0,0 +1,192 @@
+use crate::InputFile;
+use crate::state::FinalState;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// The name used when `--save-session` or `--resume` is given without one.
+pub const DEFAULT_NAME: &str = "default";
+
+/// Everything needed to reopen jq-repl where it was left off.
+///
+/// Sessions are stored in `$XDG_DATA_HOME/jq-repl/sessions/<name>/`, as a `session.json` file
+/// along with copies of any inputs that wouldn't survive the end of the session (like standard
+/// input).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    pub query: String,
+    /// The prompt, which holds the runtime flags and the active lens
+    pub prompt: String,
+    pub raw_input: bool,
+    pub null_input: bool,
+    /// Extra arguments passed to jq, like `--arg name value`
+    pub jq_args: Vec<String>,
+    pub inputs: Vec<PathBuf>,
+}
+
+impl Session {
+    /// Load a saved session.
+    pub fn load(name: &str) -> Result<Self, crate::Error> {
+        let path = session_dir(name)?.join("session.json");
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(crate::Error::NoSession(name.to_string()));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|source| {
+            crate::Error::SessionFormat {
+                name: name.to_string(),
+                source,
+            }
+        })
+    }
+
+    /// Save the session under `name`, copying inputs that only exist for this run.
+    pub fn save(self, name: &str, files: &[InputFile]) -> Result<(), crate::Error> {
+        self.save_in(&session_dir(name)?, files)
+    }
+
+    /// Save the session in `dir`, which sits next to the other sessions.
+    ///
+    /// Inputs copied by another session (when it was resumed) are copied again, so deleting that
+    /// session doesn't break this one.
+    fn save_in(mut self, dir: &Path, files: &[InputFile]) -> Result<(), crate::Error> {
+        std::fs::create_dir_all(dir)?;
+        let sessions_dir = dir.parent().unwrap_or(dir);
+
+        self.inputs = vec![];
+        for (i, file) in files.iter().enumerate() {
+            if let InputFile::File(path) = file {
+                let path = std::path::absolute(path)?;
+                if !path.starts_with(sessions_dir) {
+                    self.inputs.push(path);
+                    continue;
+                }
+            }
+
+            let input = dir.join(format!("input-{i}"));
+            let mut copy = NamedTempFile::new_in(dir)?;
+            std::io::copy(&mut File::open(file.path())?, &mut copy)?;
+            copy.persist(&input)?;
+            self.inputs.push(input);
+        }
+
+        // Remove copies left over from an earlier save with more inputs
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_copy = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("input-"));
+            if is_copy && !self.inputs.contains(&path) {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        let mut session = NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut session, &self).map_err(std::io::Error::from)?;
+        writeln!(session)?;
+        session.persist(dir.join("session.json"))?;
+
+        Ok(())
+    }
+
+    /// Pick up the query and prompt that fzf was left with.
+    pub fn update(&mut self, state: &FinalState) {
+        self.prompt.clone_from(&state.prompt);
+        self.query.clone_from(&state.query);
+    }
+}
+
+/// The names of all saved sessions, most recently saved first.
+pub fn list() -> Result<Vec<String>, crate::Error> {
+    let dir = sessions_dir()?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut sessions = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let Ok(modified) = entry.path().join("session.json").metadata() else {
+            continue;
+        };
+        sessions.push((modified.modified()?, entry.file_name()));
+    }
+
+    sessions.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    Ok(sessions
+        .into_iter()
+        .map(|(_, name)| name.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Check that a session (or fixture) name can be used as a directory name.
+pub fn parse_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        Err("must be a plain file name".to_string())
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+fn sessions_dir() -> Result<PathBuf, crate::Error> {
+    directories::ProjectDirs::from("", "", "jq-repl")
+        .map(|dirs| dirs.data_dir().join("sessions"))
+        .ok_or_else(|| std::io::Error::other("no home directory to save sessions in").into())
+}
+
+fn session_dir(name: &str) -> Result<PathBuf, crate::Error> {
+    Ok(sessions_dir()?.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_inputs_of_resumed_session() {
+        let sessions = tempfile::tempdir().unwrap();
+        let (first, second) = (
+            sessions.path().join("first"),
+            sessions.path().join("second"),
+        );
+        std::fs::create_dir(&first).unwrap();
+        let copied = first.join("input-0");
+        std::fs::write(&copied, "{}").unwrap();
+        let outside = tempfile::NamedTempFile::new().unwrap();
+
+        let session = Session {
+            query: ".".to_string(),
+            prompt: "> ".to_string(),
+            raw_input: false,
+            null_input: false,
+            jq_args: vec![],
+            inputs: vec![],
+        };
+        let files = [InputFile::File(&copied), InputFile::File(outside.path())];
+        session.save_in(&second, &files).unwrap();
+        std::fs::remove_dir_all(&first).unwrap();
+
+        let file = File::open(second.join("session.json")).unwrap();
+        let saved: Session = serde_json::from_reader(file).unwrap();
+        assert_eq!(
+            saved.inputs,
+            [second.join("input-0"), outside.path().to_path_buf()]
+        );
+        assert_eq!(std::fs::read_to_string(&saved.inputs[0]).unwrap(), "{}");
+    }
+
+    #[test]
+    fn rejects_names_outside_sessions_dir() {
+        assert!(parse_name("work").is_ok());
+        assert!(parse_name("../work").is_err());
+        assert!(parse_name(".hidden").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::ask;
+use crate::opt::SnippetsCommand;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// A saved query.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Snippet {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Named query snippets, stored as a JSON object in `$XDG_DATA_HOME/jq-repl/snippets.json`.
+#[derive(Debug)]
+pub struct Snippets {
+    path: PathBuf,
+    snippets: BTreeMap<String, Snippet>,
+}
+
+impl Snippets {
+    /// Where snippets are stored by default.
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "jq-repl")
+            .map(|dirs| dirs.data_dir().join("snippets.json"))
+    }
+
+    /// Load the snippets stored at `path`, which may not exist yet.
+    pub fn load(path: &Path) -> Result<Self, SnippetsError> {
+        let snippets = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            snippets,
+        })
+    }
+
+    /// Write the snippets back to where they were loaded from, replacing the file at once.
+    pub fn save(&self) -> Result<(), SnippetsError> {
+        let dir = self.path.parent().unwrap_or_else(|| ".".as_ref());
+        std::fs::create_dir_all(dir)?;
+
+        let mut file = NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut file, &self.snippets)?;
+        writeln!(file)?;
+        file.persist(&self.path)?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Snippet> {
+        self.snippets.get(name)
+    }
+
+    /// Add a snippet, replacing any with the same name.
+    pub fn insert(&mut self, name: String, snippet: Snippet) {
+        self.snippets.insert(name, snippet);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Snippet> {
+        self.snippets.remove(name)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    /// One line per snippet (optionally only those with `tag`), for a person or fzf to read.
+    ///
+    /// Lines hold the name, description, tags and query, separated by tabs. The name comes first,
+    /// so a picked line can be looked up again.
+    pub fn lines(&self, tag: Option<&str>) -> impl Iterator<Item = String> {
+        self.snippets
+            .iter()
+            .filter(move |(_, snippet)| tag.is_none_or(|tag| snippet.tags.iter().any(|t| t == tag)))
+            .map(|(name, snippet)| {
+                let tags = snippet
+                    .tags
+                    .iter()
+                    .map(|tag| format!("#{tag}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                [name.as_str(), &snippet.description, &tags, &snippet.query]
+                    .map(|field| field.replace(['\t', '\n'], " "))
+                    .join("\t")
+            })
+    }
+
+    /// All snippets as a pretty-printed JSON object keyed by name.
+    pub fn export(&self, writer: impl Write) -> Result<(), SnippetsError> {
+        serde_json::to_writer_pretty(writer, &self.snippets)?;
+        Ok(())
+    }
+}
+
+/// Run a `jq-repl snippets` subcommand.
+pub(crate) fn run_command(command: SnippetsCommand) -> Result<(), SnippetsError> {
+    let path = Snippets::default_path().ok_or(SnippetsError::NoDataDir)?;
+    let mut snippets = Snippets::load(&path)?;
+
+    match command {
+        SnippetsCommand::List { tag } => {
+            let mut stdout = std::io::stdout().lock();
+            for line in snippets.lines(tag.as_deref()) {
+                writeln!(stdout, "{line}")?;
+            }
+        }
+        SnippetsCommand::Add {
+            name,
+            description,
+            tags,
+            query,
+        } => {
+            let (name, description, tags) = match name {
+                Some(name) => (name, description.unwrap_or_default(), tags),
+                None if std::io::stdin().is_terminal() => {
+                    let name = ask("Name")?;
+                    let description = match description {
+                        Some(description) => description,
+                        None => ask("Description (optional)")?,
+                    };
+                    let tags = if tags.is_empty() {
+                        ask("Tags (separated by spaces, optional)")?
+                            .split_whitespace()
+                            .map(String::from)
+                            .collect()
+                    } else {
+                        tags
+                    };
+                    (name, description, tags)
+                }
+                None => return Err(SnippetsError::NoName),
+            };
+            if name.is_empty() {
+                return Err(SnippetsError::NoName);
+            }
+
+            snippets.insert(
+                name,
+                Snippet {
+                    query,
+                    description,
+                    tags,
+                },
+            );
+            snippets.save()?;
+        }
+        SnippetsCommand::Rm { names } => {
+            for name in names {
+                snippets
+                    .remove(&name)
+                    .ok_or(SnippetsError::NoSnippet(name))?;
+            }
+            snippets.save()?;
+        }
+        SnippetsCommand::Export => {
+            let mut stdout = std::io::stdout().lock();
+            snippets.export(&mut stdout)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The name of the snippet on a line from [`Snippets::lines`].
+#[must_use]
+pub fn name_from_line(line: &str) -> &str {
+    line.split('\t').next().unwrap_or_default()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SnippetsError {
+    #[error("failed to access snippets: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse snippets: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("failed to save snippets: {0}")]
+    Persist(#[from] tempfile::PersistError),
+    #[error("no home directory to save snippets in")]
+    NoDataDir,
+    #[error("a name is needed to save a snippet")]
+    NoName,
+    #[error("no snippet named {0:?}")]
+    NoSnippet(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(query: &str, tags: &[&str]) -> Snippet {
+        Snippet {
+            query: query.to_string(),
+            description: String::new(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/snippets.json");
+
+        let mut snippets = Snippets::load(&path).unwrap();
+        snippets.insert("names".to_string(), snippet(".[].name", &["users"]));
+        snippets.save().unwrap();
+
+        let snippets = Snippets::load(&path).unwrap();
+        assert_eq!(
+            snippets.get("names"),
+            Some(&snippet(".[].name", &["users"]))
+        );
+    }
+
+    #[test]
+    fn lists_by_tag() {
+        let mut snippets = Snippets::load(Path::new("/nonexistent/snippets.json")).unwrap();
+        snippets.insert("a".to_string(), snippet(".a\t| keys", &["x", "y"]));
+        snippets.insert("b".to_string(), snippet(".b", &[]));
+
+        assert_eq!(
+            snippets.lines(None).collect::<Vec<_>>(),
+            ["a\t\t#x #y\t.a | keys", "b\t\t\t.b"]
+        );
+        assert_eq!(snippets.lines(Some("y")).count(), 1);
+        assert_eq!(name_from_line("a\t\t#x #y\t.a | keys"), "a");
+    }
+}
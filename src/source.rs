@@ -0,0 +1,179 @@
+use std::io::{IsTerminal, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Inputs smaller than this are read without showing any progress.
+const PROGRESS_THRESHOLD: u64 = 8 * 1024 * 1024;
+/// How often the progress line is redrawn.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What kind of file an input path points to, which decides how it's read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// A regular file, which jq can read directly (and again later)
+    Regular,
+    /// A named pipe, like the ones made by process substitution (`<(cmd)`)
+    Fifo,
+    /// A character device, like `/dev/stdin`
+    CharDevice,
+    /// A directory, socket or anything else that can't be read as a stream of bytes
+    Other,
+    /// Nothing exists at the path
+    Missing,
+}
+
+impl SourceKind {
+    /// Classify the file at `path`, following symlinks.
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let file_type = match std::fs::metadata(path) {
+            Ok(metadata) => metadata.file_type(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::Missing),
+            Err(err) => return Err(err),
+        };
+
+        Ok(if file_type.is_file() {
+            Self::Regular
+        } else if file_type.is_fifo() {
+            Self::Fifo
+        } else if file_type.is_char_device() {
+            Self::CharDevice
+        } else {
+            Self::Other
+        })
+    }
+
+    /// Whether the input has to be copied, since it can only be read once.
+    #[must_use]
+    pub fn is_stream(self) -> bool {
+        matches!(self, Self::Fifo | Self::CharDevice)
+    }
+}
+
+/// Shows how much of a stream has been read on stderr, once it gets large.
+///
+/// Nothing is shown unless stderr is a terminal, and the line is cleared once the stream ends.
+pub struct Progress<'a, R> {
+    inner: R,
+    name: &'a Path,
+    read: u64,
+    last_shown: Option<Instant>,
+    enabled: bool,
+}
+
+impl<'a, R: Read> Progress<'a, R> {
+    pub fn new(inner: R, name: &'a Path) -> Self {
+        Self {
+            inner,
+            name,
+            read: 0,
+            last_shown: None,
+            enabled: std::io::stderr().is_terminal(),
+        }
+    }
+
+    fn show(&mut self) {
+        if self.read < PROGRESS_THRESHOLD
+            || self
+                .last_shown
+                .is_some_and(|shown| shown.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_shown = Some(Instant::now());
+        let _ = write!(
+            std::io::stderr(),
+            "\r\x1b[Kreading {}: {}",
+            self.name.display(),
+            human_size(self.read)
+        );
+    }
+}
+
+impl<R> Progress<'_, R> {
+    fn clear(&mut self) {
+        if self.last_shown.take().is_some() {
+            let _ = write!(std::io::stderr(), "\r\x1b[K");
+        }
+    }
+}
+
+impl<R: Read> Read for Progress<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if self.enabled {
+            self.read += len as u64;
+            if len == 0 {
+                self.clear();
+            } else {
+                self.show();
+            }
+        }
+        Ok(len)
+    }
+}
+
+impl<R> Drop for Progress<'_, R> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "the size is only shown with one decimal"
+)]
+fn human_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["B", "KiB", "MiB", "GiB"] {
+        if size < 1024.0 {
+            return format!("{size:.1} {unit}");
+        }
+        size /= 1024.0;
+    }
+    format!("{size:.1} TiB")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("input.json");
+        std::fs::write(&file, "{}").unwrap();
+
+        assert_eq!(SourceKind::of(&file).unwrap(), SourceKind::Regular);
+        assert_eq!(SourceKind::of(dir.path()).unwrap(), SourceKind::Other);
+        assert_eq!(
+            SourceKind::of(&dir.path().join("missing.json")).unwrap(),
+            SourceKind::Missing
+        );
+        assert_eq!(
+            SourceKind::of(Path::new("/dev/null")).unwrap(),
+            SourceKind::CharDevice
+        );
+    }
+
+    #[test]
+    fn classifies_named_pipes() {
+        let dir = tempfile::tempdir().unwrap();
+        let fifo = dir.path().join("fifo");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        assert_eq!(SourceKind::of(&fifo).unwrap(), SourceKind::Fifo);
+        assert!(SourceKind::Fifo.is_stream());
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(human_size(512), "512.0 B");
+        assert_eq!(human_size(12 * 1024 * 1024 + 300 * 1024), "12.3 MiB");
+    }
+}
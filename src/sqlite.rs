@@ -0,0 +1,170 @@
+use crate::format::{BinaryFormat, FormatError, float_to_json};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::Path;
+
+/// Which rows of a SQLite database to read.
+#[derive(Debug, Clone, Copy)]
+pub enum Query<'a> {
+    /// Every row of one table
+    Table(&'a str),
+    /// The rows returned by a SQL statement
+    Sql(&'a str),
+    /// Every table, as an object of arrays keyed by table name
+    AllTables,
+}
+
+/// Write the rows of a SQLite database as JSON objects keyed by column name.
+///
+/// Rows are written as one array, or as a stream of objects with `stream`. The database is opened
+/// read-only, so `--sql` can't change it by accident.
+pub fn to_json(
+    db: &Path,
+    query: Query,
+    binary: BinaryFormat,
+    stream: bool,
+    mut writer: impl Write,
+) -> Result<(), FormatError> {
+    let connection = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let value = match query {
+        Query::Table(table) => rows(&connection, &select_all(table), binary)?,
+        Query::Sql(sql) => rows(&connection, sql, binary)?,
+        Query::AllTables => {
+            let mut tables = Map::new();
+            for table in table_names(&connection)? {
+                let rows = rows(&connection, &select_all(&table), binary)?;
+                tables.insert(table, Value::Array(rows));
+            }
+            serde_json::to_writer(&mut writer, &tables)?;
+            writeln!(writer)?;
+            return Ok(());
+        }
+    };
+
+    if stream {
+        for row in value {
+            serde_json::to_writer(&mut writer, &row)?;
+            writeln!(writer)?;
+        }
+    } else {
+        serde_json::to_writer(&mut writer, &value)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+fn select_all(table: &str) -> String {
+    format!("SELECT * FROM \"{}\"", table.replace('"', "\"\""))
+}
+
+fn table_names(connection: &Connection) -> Result<Vec<String>, FormatError> {
+    let mut statement = connection.prepare(
+        "SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY \
+         name",
+    )?;
+    let names = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(names)
+}
+
+fn rows(
+    connection: &Connection,
+    sql: &str,
+    binary: BinaryFormat,
+) -> Result<Vec<Value>, FormatError> {
+    let mut statement = connection.prepare(sql)?;
+    let columns = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    let mut rows = statement.query([])?;
+    let mut values = vec![];
+    while let Some(row) = rows.next()? {
+        let mut object = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            object.insert(column.clone(), to_value(row.get_ref(i)?, binary));
+        }
+        values.push(Value::Object(object));
+    }
+
+    Ok(values)
+}
+
+fn to_value(value: ValueRef, binary: BinaryFormat) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => n.into(),
+        ValueRef::Real(f) => float_to_json(f),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned().into(),
+        ValueRef::Blob(bytes) => binary.encode(bytes.to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let connection = Connection::open(file.path()).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE users (id INTEGER, name TEXT, score REAL, avatar BLOB);
+                 INSERT INTO users VALUES (1, 'ada', 9.5, x'0102'), (2, 'bob', NULL, NULL);
+                 CREATE TABLE \"odd \"\"name\"\"\" (n INTEGER);
+                 INSERT INTO \"odd \"\"name\"\"\" VALUES (3);",
+            )
+            .unwrap();
+        file
+    }
+
+    fn convert(query: Query, stream: bool) -> String {
+        let db = database();
+        let mut output = vec![];
+        to_json(db.path(), query, BinaryFormat::Array, stream, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn table_as_array() {
+        assert_eq!(
+            convert(Query::Table("users"), false),
+            "[{\"id\":1,\"name\":\"ada\",\"score\":9.5,\"avatar\":[1,2]},{\"id\":2,\"name\":\"bob\",\
+             \"score\":null,\"avatar\":null}]\n"
+        );
+    }
+
+    #[test]
+    fn sql_as_stream() {
+        assert_eq!(
+            convert(Query::Sql("SELECT name FROM users ORDER BY id DESC"), true),
+            "{\"name\":\"bob\"}\n{\"name\":\"ada\"}\n"
+        );
+    }
+
+    #[test]
+    fn all_tables_by_name() {
+        let output = convert(Query::AllTables, false);
+        assert!(output.starts_with("{\"odd \\\"name\\\"\":[{\"n\":3}],\"users\":[{\"id\":1,"));
+    }
+
+    #[test]
+    fn opened_read_only() {
+        let db = database();
+        let result = to_json(
+            db.path(),
+            Query::Sql("DELETE FROM users"),
+            BinaryFormat::Array,
+            false,
+            std::io::sink(),
+        );
+        assert!(matches!(result, Err(FormatError::Sqlite(_))));
+    }
+}
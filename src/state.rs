@@ -0,0 +1,109 @@
+use crate::bash_quote;
+use std::path::Path;
+
+/// Keys that quit fzf, along with the action they normally run.
+///
+/// Enter is left out, since the state is written by `_jq-repl-transform --accept` instead.
+const EXIT_BINDINGS: [(&str, &str); 4] = [
+    ("esc", "abort"),
+    ("ctrl-c", "abort"),
+    ("ctrl-g", "abort"),
+    ("ctrl-q", "abort"),
+];
+
+/// Where fzf was left when it quit, recorded by the [`exit_bindings`].
+///
+/// The state file holds the action, prompt and query, one per line.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FinalState {
+    /// Whether the query was accepted (with enter), rather than aborted
+    pub accepted: bool,
+    /// The prompt, which holds the runtime flags and the active lens
+    pub prompt: String,
+    pub query: String,
+}
+
+impl FinalState {
+    /// Read the state file written by the [`exit_bindings`].
+    ///
+    /// The file is missing if fzf quit some other way, like being killed.
+    pub fn read(state_file: &Path) -> std::io::Result<Option<Self>> {
+        let state = match std::fs::read_to_string(state_file) {
+            Ok(state) => state,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        let mut lines = state.splitn(3, '\n');
+        let (Some(action), Some(prompt), Some(query)) = (lines.next(), lines.next(), lines.next())
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            accepted: action == "accept",
+            prompt: prompt.to_string(),
+            query: query.strip_suffix('\n').unwrap_or(query).to_string(),
+        }))
+    }
+
+    /// Write the state file the same way the [`exit_bindings`] do.
+    pub fn write(&self, state_file: &Path) -> std::io::Result<()> {
+        let action = if self.accepted { "accept" } else { "abort" };
+        std::fs::write(
+            state_file,
+            format!("{action}\n{}\n{}\n", self.prompt, self.query),
+        )
+    }
+}
+
+/// Bindings that record the query and prompt to `state_file` before quitting fzf.
+#[must_use]
+pub fn exit_bindings(state_file: &Path) -> Vec<String> {
+    let state_file = bash_quote(state_file);
+    EXIT_BINDINGS
+        .iter()
+        .map(|(key, action)| {
+            format!(
+                "--bind={key}:execute-silent(printf \"%s\\n\" {action} \"$FZF_PROMPT\" {{q}} > \
+                 {state_file})+{action}"
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_state_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state");
+        assert_eq!(FinalState::read(&state_file).unwrap(), None);
+
+        std::fs::write(&state_file, "accept\n-c gron> \n.items[] | {a}\n").unwrap();
+        let state = FinalState {
+            accepted: true,
+            prompt: "-c gron> ".to_string(),
+            query: ".items[] | {a}".to_string(),
+        };
+        assert_eq!(
+            FinalState::read(&state_file).unwrap().as_ref(),
+            Some(&state)
+        );
+
+        state.write(&state_file).unwrap();
+        assert_eq!(FinalState::read(&state_file).unwrap(), Some(state));
+    }
+
+    #[test]
+    fn bindings_keep_exit_action() {
+        let bindings = exit_bindings(Path::new("/tmp/state"));
+        assert_eq!(
+            bindings[0],
+            "--bind=esc:execute-silent(printf \"%s\\n\" abort \"$FZF_PROMPT\" {q} > \
+             /tmp/state)+abort"
+        );
+    }
+}
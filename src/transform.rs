@@ -1,6 +1,20 @@
 use crate::Prompt;
+use crate::snippets::Snippet;
 use std::collections::BTreeMap;
 
+/// The program name in the prompt that shows how the output differs between two inputs, unless a
+/// lens has the same name.
+pub const DIFF_PROGRAM: &str = "diff";
+
+/// The program name in the prompt that shows how the output differs from the pinned output, unless
+/// a lens has the same name.
+pub const PINNED_PROGRAM: &str = "pinned";
+
+/// The preview window while editing a query, taking up (almost) the whole screen.
+pub const DEFAULT_PREVIEW_WINDOW: &str = "up,99%,border-bottom";
+/// The preview window while the snippet picker is open, leaving room for the list below it.
+const PICKER_PREVIEW_WINDOW: &str = "up,50%,border-bottom";
+
 /// Configuration for building fzf transform actions.
 ///
 /// All fields are sourced from environment variables set by `jq-repl` at startup, so they are
@@ -10,6 +24,12 @@ pub struct TransformConfig {
     pub jq_arg_prefix: String,
     pub color_flag: String,
     pub no_color_flag: String,
+    /// Program that compares the query's output between inputs
+    pub diff_bin: String,
+    /// Where the pinned output is saved
+    pub pin_file: String,
+    /// Whether jq-repl set the command printing the preview label (`JQ_REPL_PREVIEW_LABEL`)
+    pub preview_label: bool,
     /// Lens commands keyed by name, sourced from `JQ_REPL_LENS_<NAME>` env vars.
     pub lenses: BTreeMap<String, String>,
     pub input_file_paths: String,
@@ -32,6 +52,9 @@ impl TransformConfig {
             jq_arg_prefix: std::env::var("JQ_REPL_JQ_ARG_PREFIX").unwrap_or_default(),
             color_flag: std::env::var("JQ_REPL_COLOR_FLAG").unwrap_or_default(),
             no_color_flag: std::env::var("JQ_REPL_NO_COLOR_FLAG").unwrap_or_default(),
+            diff_bin: std::env::var("JQ_REPL_DIFF_BIN").unwrap_or_else(|_| "_jq-repl-diff".into()),
+            pin_file: std::env::var("JQ_REPL_PIN_FILE").unwrap_or_default(),
+            preview_label: std::env::var_os("JQ_REPL_PREVIEW_LABEL").is_some(),
             lenses,
             input_file_paths,
         })
@@ -40,14 +63,40 @@ impl TransformConfig {
 
 /// Build the fzf action string for the given prompt state and config.
 ///
-/// Returns a `change-prompt(...)+change-preview(...)` string suitable for use as the output of
-/// fzf's `transform:` action.
+/// Returns a `change-prompt(...)+change-preview:...` string suitable for use as the output of
+/// fzf's `transform:` action. When jq-repl set a label command, the label is worked out again in
+/// between, since it shows whether the output matches the pinned output.
 #[must_use]
 pub fn transform_actions(prompt: &Prompt, config: &TransformConfig) -> String {
+    let mut actions = format!("change-prompt({prompt})");
+    if config.preview_label {
+        actions.push_str("+transform-preview-label(bash -c \"$JQ_REPL_PREVIEW_LABEL\")");
+    }
+    // Last, so parentheses in the command (like in file names) don't end it early
+    format!(
+        "{actions}+change-preview:{}",
+        preview_command(prompt, config)
+    )
+}
+
+fn preview_command(prompt: &Prompt, config: &TransformConfig) -> String {
     let pipe: Option<&str> = prompt
         .program()
         .and_then(|name| config.lenses.get(name).map(String::as_str));
 
+    if pipe.is_none() {
+        let diff_bin = &config.diff_bin;
+        let files = &config.input_file_paths;
+        match prompt.program() {
+            Some(DIFF_PROGRAM) => return format!("{diff_bin} {{q}} {files}"),
+            Some(PINNED_PROGRAM) => {
+                let pin_file = crate::bash_quote(&config.pin_file);
+                return format!("{diff_bin} --pinned {pin_file} {{q}} {files}");
+            }
+            _ => {}
+        }
+    }
+
     let color_flag = if pipe.is_some() {
         config.no_color_flag.as_str()
     } else {
@@ -69,10 +118,98 @@ pub fn transform_actions(prompt: &Prompt, config: &TransformConfig) -> String {
 
     let jq_bin = &config.jq_bin;
     let jq_params = parts.join(" ");
-    let jq_cmd = match pipe {
+    match pipe {
         Some(p) => format!("{jq_bin} {jq_params} | {p}"),
         None => format!("{jq_bin} {jq_params}"),
-    };
+    }
+}
 
-    format!("change-prompt({prompt})+change-preview({jq_cmd})")
+/// Open the snippet picker in fzf's list, or close it if it's already open.
+///
+/// Without any snippets, there's nothing to open, so the preview label says so instead.
+#[must_use]
+pub fn toggle_snippet_picker(picker_open: bool, has_snippets: bool) -> String {
+    if picker_open {
+        close_snippet_picker()
+    } else if !has_snippets {
+        "change-preview-label( no snippets saved yet )".to_string()
+    } else {
+        format!(
+            "reload(\"$JQ_REPL_BIN\" snippets list)+change-preview-window({PICKER_PREVIEW_WINDOW})\
+             +first"
+        )
+    }
+}
+
+/// Close the snippet picker and put the snippet's query in the prompt.
+#[must_use]
+pub fn load_snippet(snippet: &Snippet) -> String {
+    // change-query has to come last, so the query doesn't need any delimiters
+    format!(
+        "{}+change-query:{}",
+        close_snippet_picker(),
+        snippet.query.replace('\n', " ")
+    )
+}
+
+fn close_snippet_picker() -> String {
+    format!("reload(true)+change-preview-window({DEFAULT_PREVIEW_WINDOW})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_follows_the_lens() {
+        let mut config = TransformConfig {
+            jq_bin: "gojq".to_string(),
+            jq_arg_prefix: String::new(),
+            color_flag: "-C".to_string(),
+            no_color_flag: "-M".to_string(),
+            diff_bin: "_jq-repl-diff".to_string(),
+            pin_file: "/tmp/pinned".to_string(),
+            preview_label: false,
+            lenses: BTreeMap::new(),
+            input_file_paths: "'a (copy).json'".to_string(),
+        };
+        let prompt = "pinned> ".parse().unwrap();
+        assert_eq!(
+            transform_actions(&prompt, &config),
+            "change-prompt(pinned> )+change-preview:_jq-repl-diff --pinned /tmp/pinned {q} 'a \
+             (copy).json'"
+        );
+
+        config.preview_label = true;
+        let prompt = "-c> ".parse().unwrap();
+        assert_eq!(
+            transform_actions(&prompt, &config),
+            "change-prompt(-c> )+transform-preview-label(bash -c \"$JQ_REPL_PREVIEW_LABEL\")\
+             +change-preview:gojq -C -c {q} 'a (copy).json'"
+        );
+    }
+
+    #[test]
+    fn picker_says_when_there_are_no_snippets() {
+        assert_eq!(
+            toggle_snippet_picker(false, false),
+            "change-preview-label( no snippets saved yet )"
+        );
+        assert!(toggle_snippet_picker(false, true).starts_with("reload(\"$JQ_REPL_BIN\""));
+        assert_eq!(toggle_snippet_picker(true, true), close_snippet_picker());
+    }
+
+    #[test]
+    fn snippet_query_is_not_delimited() {
+        let snippet = Snippet {
+            query: ".a | select(.b == \")\")".to_string(),
+            description: String::new(),
+            tags: vec![],
+        };
+        assert_eq!(
+            load_snippet(&snippet),
+            "reload(true)+change-preview-window(up,99%,border-bottom)+change-query:.a | select(.b \
+             == \")\")"
+        );
+    }
 }
@@ -0,0 +1,224 @@
+use serde::de::IgnoredAny;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// How much of the line around an error is shown on either side of it.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// What to do when JSON input turns out to be malformed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Validation {
+    /// Stop with the location of the first error
+    Strict,
+    /// Continue with the values before the first error
+    Prefix,
+    /// Continue with the lines that are valid JSON on their own, for NDJSON
+    Lines,
+}
+
+/// Where a JSON input stopped being valid.
+#[derive(Debug, thiserror::Error)]
+#[error("{}:{line}:{column}: {message}\n{snippet}", path.display())]
+pub struct InvalidJson {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The offending line, with a marker under the column
+    pub snippet: String,
+}
+
+impl InvalidJson {
+    fn new(path: &Path, contents: &Path, err: &serde_json::Error, line_offset: usize) -> Self {
+        let line = err.line() + line_offset;
+        let snippet = read_line(contents, line)
+            .map(|text| snippet(&text, err.column()))
+            .unwrap_or_default();
+
+        // serde_json appends the location to its messages, which is shown separately here
+        let message = err.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            line,
+            column: err.column(),
+            message,
+            snippet,
+        }
+    }
+}
+
+/// Check that the JSON file at `contents` is well formed.
+///
+/// Unless `validation` is [`Validation::Strict`], malformed input is repaired instead of rejected,
+/// returning the repaired copy along with the error that was worked around. `path` is the name the
+/// input was given as, for error messages.
+pub fn validate(
+    validation: Validation,
+    path: &Path,
+    contents: &Path,
+) -> Result<Option<(NamedTempFile, InvalidJson)>, crate::Error> {
+    match validation {
+        Validation::Strict | Validation::Prefix => {
+            let mut values =
+                serde_json::Deserializer::from_reader(BufReader::new(File::open(contents)?))
+                    .into_iter::<IgnoredAny>();
+
+            let Some(Err(err)) = values.find(Result::is_err) else {
+                return Ok(None);
+            };
+            if err.is_io() {
+                return Err(std::io::Error::from(err).into());
+            }
+
+            let invalid = InvalidJson::new(path, contents, &err, 0);
+            if validation == Validation::Strict {
+                return Err(invalid.into());
+            }
+
+            let valid_len = values.byte_offset() as u64;
+            let mut prefix = NamedTempFile::new()?;
+            std::io::copy(&mut File::open(contents)?.take(valid_len), &mut prefix)?;
+            Ok(Some((prefix, invalid)))
+        }
+        Validation::Lines => {
+            let mut valid = NamedTempFile::new()?;
+            let mut first_invalid = None;
+
+            for (i, line) in BufReader::new(File::open(contents)?)
+                .split(b'\n')
+                .enumerate()
+            {
+                let line = line?;
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+
+                match serde_json::from_slice::<IgnoredAny>(&line) {
+                    Ok(_) => {
+                        valid.write_all(&line)?;
+                        valid.write_all(b"\n")?;
+                    }
+                    Err(err) if first_invalid.is_none() => {
+                        first_invalid = Some(InvalidJson::new(path, contents, &err, i));
+                    }
+                    Err(_) => {}
+                }
+            }
+
+            Ok(first_invalid.map(|invalid| (valid, invalid)))
+        }
+    }
+}
+
+/// Read the 1-based `line` of a file, if it exists.
+fn read_line(path: &Path, line: usize) -> Option<String> {
+    let text = BufReader::new(File::open(path).ok()?)
+        .split(b'\n')
+        .nth(line.checked_sub(1)?)?
+        .ok()?;
+    Some(String::from_utf8_lossy(&text).into_owned())
+}
+
+/// Show `text` around the 1-based `column`, with a marker under it.
+fn snippet(text: &str, column: usize) -> String {
+    let text = text.trim_end_matches('\r');
+    let chars: Vec<char> = text.chars().collect();
+    let column = column.saturating_sub(1).min(chars.len());
+
+    let start = column.saturating_sub(SNIPPET_CONTEXT);
+    let end = (column + SNIPPET_CONTEXT).min(chars.len());
+    let ellipsis = if start > 0 { "…" } else { "" };
+
+    let before: String = chars[start..column].iter().collect();
+    let rest: String = chars[column..end].iter().collect();
+    let trailing = if end < chars.len() { "…" } else { "" };
+
+    format!(
+        "    {ellipsis}{before}{rest}{trailing}\n    {}^",
+        " ".repeat(ellipsis.chars().count() + before.chars().count())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(contents: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn check(validation: Validation, contents: &str) -> Result<Option<String>, crate::Error> {
+        let file = input(contents);
+        let repaired = validate(validation, Path::new("input.json"), file.path())?;
+        Ok(repaired.map(|(file, _)| std::fs::read_to_string(file.path()).unwrap()))
+    }
+
+    #[test]
+    fn accepts_valid_streams() {
+        assert!(
+            check(Validation::Strict, "{\"a\": 1}\n[2]\n")
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            check(Validation::Lines, "{\"a\": 1}\n\n[2]\n")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn reports_location_and_snippet() {
+        let Err(crate::Error::InvalidJson(err)) =
+            check(Validation::Strict, "{\"a\": 1}\n{\"b\": }\n")
+        else {
+            panic!("expected invalid JSON");
+        };
+
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(err.message, "expected value");
+        assert_eq!(
+            err.to_string(),
+            "input.json:2:7: expected value\n    {\"b\": }\n          ^"
+        );
+    }
+
+    #[test]
+    fn keeps_valid_prefix() {
+        let repaired = check(Validation::Prefix, "1\n[2]\n{\"b\": }\n4\n").unwrap();
+        assert_eq!(repaired.as_deref(), Some("1\n[2]\n"));
+    }
+
+    #[test]
+    fn keeps_valid_lines() {
+        let file = input("{\"a\": 1}\nnot json\n{\"a\": 2}\n");
+        let (repaired, invalid) = validate(Validation::Lines, Path::new("-"), file.path())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(repaired.path()).unwrap(),
+            "{\"a\": 1}\n{\"a\": 2}\n"
+        );
+        assert_eq!((invalid.line, invalid.column), (2, 2));
+    }
+
+    #[test]
+    fn snippet_is_cut_around_column() {
+        let line = format!("{}!{}", "a".repeat(100), "b".repeat(100));
+        let snippet = snippet(&line, 101);
+        let (text, marker) = snippet.split_once('\n').unwrap();
+
+        assert_eq!(text.chars().count(), 4 + 1 + 80 + 1);
+        assert_eq!(text.chars().nth(marker.len() - 1), Some('!'));
+    }
+}
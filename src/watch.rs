@@ -0,0 +1,108 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// How long to wait for a burst of events to settle, e.g. when an editor writes to a temporary
+/// file and renames it over the original.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Start watching the given files for changes.
+///
+/// The returned watcher must be kept alive for as long as changes should be reported. Once it's
+/// dropped, iterating over [`Changes`] ends.
+pub fn watch(
+    paths: impl IntoIterator<Item = PathBuf>,
+) -> notify::Result<(notify::RecommendedWatcher, Changes)> {
+    let (sender, events) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    let paths = paths
+        .into_iter()
+        .map(|path| std::fs::canonicalize(&path).map_err(notify::Error::io))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Watch the parent directories rather than the files themselves, since editors that save by
+    // renaming a new file over the old one would otherwise leave us watching a deleted file.
+    let dirs = paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .collect::<BTreeSet<_>>();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    Ok((watcher, Changes { events, paths }))
+}
+
+/// Blocks until watched files change, yielding the indices of the files that changed.
+pub struct Changes {
+    events: Receiver<notify::Result<notify::Event>>,
+    paths: Vec<PathBuf>,
+}
+
+impl Changes {
+    fn changed_indices(&self, event: &notify::Event, changed: &mut BTreeSet<usize>) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+
+        for path in &event.paths {
+            changed.extend(
+                self.paths
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, watched)| Path::new(watched) == path)
+                    .map(|(i, _)| i),
+            );
+        }
+    }
+}
+
+impl Iterator for Changes {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut changed = BTreeSet::new();
+
+        while changed.is_empty() {
+            if let Ok(event) = self.events.recv().ok()? {
+                self.changed_indices(&event, &mut changed);
+            }
+        }
+
+        loop {
+            match self.events.recv_timeout(DEBOUNCE) {
+                Ok(Ok(event)) => self.changed_indices(&event, &mut changed),
+                Ok(Err(_)) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+
+        Some(changed.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_rename_over_watched_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let watched = dir.path().join("state.json");
+        std::fs::write(&watched, "{}").unwrap();
+        std::fs::write(dir.path().join("other.json"), "{}").unwrap();
+
+        let (_watcher, mut changes) = watch([watched.clone()]).unwrap();
+
+        std::fs::write(dir.path().join("other.json"), "[]").unwrap();
+        let replacement = dir.path().join(".state.json.swp");
+        std::fs::write(&replacement, "{\"a\":1}").unwrap();
+        std::fs::rename(&replacement, &watched).unwrap();
+
+        assert_eq!(changes.next(), Some(vec![0]));
+    }
+}